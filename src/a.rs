@@ -1,7 +1,21 @@
-use super::{Color, RenderOpts};
+use super::{
+    error::SketchError,
+    grid::Grid,
+    params::Param,
+    path::{Close, Path2, Smoothing},
+    sketch::Sketch,
+    Color, Point, Rect, RenderOpts, Tone,
+};
 use cairo::Context;
+use clap::{ArgMatches, Args as _, FromArgMatches};
 use rand::{Rng, RngCore};
-use std::{error::Error, ops::Index};
+use std::ops::Index;
+
+const NX: Param = Param::int("nx", 5..20).with_min(1.0);
+const NY: Param = Param::int("ny", 5..10).with_min(1.0);
+const STROKE: Param = Param::fixed("stroke", 30.0).with_min(0.0);
+
+const PARAMS: &[Param] = &[NX, NY, STROKE];
 
 #[derive(Debug, clap::Args)]
 pub struct Args {
@@ -9,153 +23,95 @@ pub struct Args {
     show_grid: bool,
 }
 
+pub struct A;
+
+impl Sketch for A {
+    fn name(&self) -> &'static str {
+        "a"
+    }
+
+    fn description(&self) -> &'static str {
+        "Layered bands that step across the top and bottom halves"
+    }
+
+    fn params(&self) -> &'static [Param] {
+        PARAMS
+    }
+
+    fn augment_args(&self, cmd: clap::Command) -> clap::Command {
+        Args::augment_args(cmd)
+    }
+
+    fn render(
+        &self,
+        opts: &dyn RenderOpts,
+        ctx: &Context,
+        args: &ArgMatches,
+    ) -> Result<(), SketchError> {
+        let args =
+            Args::from_arg_matches(args).map_err(|e| SketchError::invalid_args(self.name(), e))?;
+        render(opts, ctx, &args)
+    }
+}
+
 #[derive(Debug)]
 struct Series {
     width: f64,
     height: f64,
-    pts: Vec<(f64, f64)>,
+    pts: Vec<Point>,
 }
 
 impl Series {
-    fn new(width: f64, height: f64, pts: Vec<(f64, f64)>) -> Self {
+    fn new(width: f64, height: f64, pts: Vec<Point>) -> Self {
         Series { width, height, pts }
     }
 
-    #[allow(dead_code)]
-    fn translate(&self, dx: f64, dy: f64) -> Series {
+    fn translate(&self, d: Point) -> Series {
         Series::new(
             self.width,
             self.height,
-            self.pts
-                .iter()
-                .map(|(x, y)| (*x + dx, *y + dy))
-                .collect::<Vec<_>>(),
+            self.pts.iter().map(|&p| p + d).collect::<Vec<_>>(),
         )
     }
 
-    fn gen_on_grid(rng: &mut dyn RngCore, grid: &Grid, width: f64, height: f64) -> Series {
-        let dw = width / grid.nw() as f64;
-        let dh = height / grid.nh() as f64;
-
-        let ow = dw / 2.0;
-        let oh = dh / 2.0;
-
+    fn gen_on_grid(rng: &mut dyn RngCore, grid: &Grid) -> Series {
+        let ny = grid.ny() as i32;
         Series::new(
-            width,
-            height,
-            (0..grid.nw())
-                .map(|i| {
-                    let j = rng.gen_range(0..grid.nh());
-                    (ow + dw * i as f64, oh + dh * j as f64)
-                })
+            grid.frame().width(),
+            grid.frame().height(),
+            (0..grid.nx())
+                .map(|i| grid.point(i, rng.gen_range(0..ny) as usize))
                 .collect::<Vec<_>>(),
         )
     }
 
-    fn stroke(&self, ctx: &Context) -> Result<(), Box<dyn Error>> {
-        ctx.new_path();
-        let (x, y) = self.pts.first().unwrap();
-        ctx.move_to(0.0, *y);
-        ctx.line_to(*x, *y);
-
-        for i in 1..self.pts.len() {
-            let (xa, ya) = self.pts[i - 1];
-            let (xb, yb) = self.pts[i];
-            let dx = xb - xa;
-
-            let ow = dx / 2.0;
-            let c = ow * 0.5523;
-            if yb - ya > dx {
-                ctx.curve_to(xa + c, ya, xa + ow, ya + ow - c, xa + ow, ya + ow);
-                ctx.line_to(xb - ow, yb - ow);
-                ctx.curve_to(xb - ow, yb - ow + c, xb - c, yb, xb, yb);
-            } else if ya - yb > dx {
-                ctx.curve_to(xa + c, ya, xa + ow, ya - ow + c, xa + ow, ya - ow);
-                ctx.line_to(xb - ow, yb + ow);
-                ctx.curve_to(xb - ow, yb + ow - c, xb - c, yb, xb, yb);
-            } else {
-                ctx.curve_to(xa + ow, ya, xb - ow, yb, xb, yb);
-            }
-        }
+    // The curve through the points, which runs out to the left and right edges.
+    fn path(&self) -> Path2 {
+        let (first, last) = (self.pts.first().unwrap(), self.pts.last().unwrap());
+        Path2::new(self.pts.clone())
+            .with_smoothing(Smoothing::RoundedStep)
+            .with_ends(Point::new(0.0, first.y()), Point::new(self.width, last.y()))
+    }
 
-        let (_, y) = self.pts.last().unwrap();
-        ctx.line_to(self.width, *y);
+    fn stroke(&self, ctx: &Context) -> Result<(), SketchError> {
+        ctx.new_path();
+        self.path().draw(ctx);
         ctx.stroke()?;
 
         Ok(())
     }
 
-    fn fill_to_bottom(&self, ctx: &Context) -> Result<(), Box<dyn Error>> {
+    fn fill_to_bottom(&self, ctx: &Context) -> Result<(), SketchError> {
         ctx.new_path();
-        let (x, y) = self.pts.first().unwrap();
-        ctx.move_to(0.0, self.height);
-        ctx.line_to(0.0, *y);
-        ctx.line_to(*x, *y);
-
-        for i in 1..self.pts.len() {
-            let (xa, ya) = self.pts[i - 1];
-            let (xb, yb) = self.pts[i];
-
-            let dx = xb - xa;
-
-            let ow = dx / 2.0;
-            let c = ow * 0.5523;
-
-            if yb - ya > dx {
-                ctx.curve_to(xa + c, ya, xa + ow, ya + ow - c, xa + ow, ya + ow);
-                ctx.line_to(xb - ow, yb - ow);
-                ctx.curve_to(xb - ow, yb - ow + c, xb - c, yb, xb, yb);
-            } else if ya - yb > dx {
-                ctx.curve_to(xa + c, ya, xa + ow, ya - ow + c, xa + ow, ya - ow);
-                ctx.line_to(xb - ow, yb + ow);
-                ctx.curve_to(xb - ow, yb + ow - c, xb - c, yb, xb, yb);
-            } else {
-                ctx.curve_to(xa + ow, ya, xb - ow, yb, xb, yb);
-            }
-        }
-
-        let (_, y) = self.pts.last().unwrap();
-        ctx.line_to(self.width, *y);
-        ctx.line_to(self.width, self.height);
-        ctx.close_path();
+        self.path().draw_closed(ctx, Close::ToY(self.height));
         ctx.fill()?;
 
         Ok(())
     }
 
-    fn fill_to_top(&self, ctx: &Context) -> Result<(), Box<dyn Error>> {
+    fn fill_to_top(&self, ctx: &Context) -> Result<(), SketchError> {
         ctx.new_path();
-        let (x, y) = self.pts.first().unwrap();
-        ctx.move_to(0.0, 0.0);
-        ctx.line_to(0.0, *y);
-        ctx.line_to(*x, *y);
-
-        for i in 1..self.pts.len() {
-            let (xa, ya) = self.pts[i - 1];
-            let (xb, yb) = self.pts[i];
-
-            let dx = xb - xa;
-
-            let ow = dx / 2.0;
-            let c = ow * 0.5523;
-
-            if yb - ya > dx {
-                ctx.curve_to(xa + c, ya, xa + ow, ya + ow - c, xa + ow, ya + ow);
-                ctx.line_to(xb - ow, yb - ow);
-                ctx.curve_to(xb - ow, yb - ow + c, xb - c, yb, xb, yb);
-            } else if ya - yb > dx {
-                ctx.curve_to(xa + c, ya, xa + ow, ya - ow + c, xa + ow, ya - ow);
-                ctx.line_to(xb - ow, yb + ow);
-                ctx.curve_to(xb - ow, yb + ow - c, xb - c, yb, xb, yb);
-            } else {
-                ctx.curve_to(xa + ow, ya, xb - ow, yb, xb, yb);
-            }
-        }
-
-        let (_, y) = self.pts.last().unwrap();
-        ctx.line_to(self.width, *y);
-        ctx.line_to(self.width, 0.0);
-        ctx.close_path();
+        self.path().draw_closed(ctx, Close::ToY(0.0));
         ctx.fill()?;
 
         Ok(())
@@ -163,84 +119,23 @@ impl Series {
 }
 
 impl Index<usize> for Series {
-    type Output = (f64, f64);
+    type Output = Point;
     fn index(&self, ix: usize) -> &Self::Output {
         self.pts.index(ix)
     }
 }
 
-#[derive(Copy, Clone)]
-struct Grid {
-    nw: i32,
-    nh: i32,
-}
-
-impl Grid {
-    fn new(nw: i32, nh: i32) -> Grid {
-        Grid { nw, nh }
-    }
-
-    fn nw(&self) -> i32 {
-        self.nw
-    }
-
-    fn nh(&self) -> i32 {
-        self.nh
-    }
-
-    fn render(&self, ctx: &Context, width: f64, height: f64) -> Result<(), Box<dyn Error>> {
-        let nw = self.nw;
-        let nh = self.nh;
-
-        let dw = width / nw as f64;
-        let dh = height / nh as f64;
-
-        ctx.save()?;
-        ctx.new_path();
-        ctx.translate(dw / 2.0, 0.0);
-        for i in 0..nw {
-            let x = dw * i as f64;
-            ctx.move_to(x, 0.0);
-            ctx.line_to(x, height);
-        }
-        ctx.set_line_width(1.0);
-        ctx.set_dash(&[1.0, 4.0], 0.0);
-        ctx.stroke()?;
-        ctx.restore()?;
-
-        ctx.save()?;
-        ctx.new_path();
-        ctx.translate(0.0, dh / 2.0);
-        for i in 0..nh {
-            let y = dh * i as f64;
-            ctx.move_to(0.0, y);
-            ctx.line_to(width, y);
-        }
-        ctx.set_line_width(1.0);
-        ctx.set_dash(&[1.0, 4.0], 0.0);
-        ctx.stroke()?;
-        ctx.restore()?;
-
-        Ok(())
-    }
-}
-
-impl std::fmt::Display for Grid {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}x{}", self.nw, self.nh)
-    }
-}
-
 fn render_series_to_top(
     ctx: &Context,
     series: &Series,
     theme: &[Color],
-) -> Result<(), Box<dyn Error>> {
+    stroke: f64,
+) -> Result<(), SketchError> {
     ctx.save()?;
     for i in 1..4 {
         let i = 4 - i;
         theme[i].set(ctx);
-        ctx.set_line_width(30.0 * i as f64);
+        ctx.set_line_width(stroke * i as f64);
         series.stroke(ctx)?;
     }
 
@@ -254,12 +149,13 @@ fn render_series_to_bottom(
     ctx: &Context,
     series: &Series,
     theme: &[Color],
-) -> Result<(), Box<dyn Error>> {
+    stroke: f64,
+) -> Result<(), SketchError> {
     ctx.save()?;
     for i in 1..4 {
         let i = 4 - i;
         theme[i].set(ctx);
-        ctx.set_line_width(30.0 * i as f64);
+        ctx.set_line_width(stroke * i as f64);
         series.stroke(ctx)?;
     }
 
@@ -269,7 +165,7 @@ fn render_series_to_bottom(
     Ok(())
 }
 
-pub fn render(opts: &dyn RenderOpts, ctx: &Context, args: &Args) -> Result<(), Box<dyn Error>> {
+pub fn render(opts: &dyn RenderOpts, ctx: &Context, args: &Args) -> Result<(), SketchError> {
     let size = opts.size();
     let width = size.width() as f64;
     let height = size.height() as f64;
@@ -285,21 +181,25 @@ pub fn render(opts: &dyn RenderOpts, ctx: &Context, args: &Args) -> Result<(), B
     ctx.rectangle(0.0, 0.0, width, height);
     ctx.fill()?;
 
-    let tgrid = Grid::new(rng.gen_range(5..20), rng.gen_range(5..10));
-    let series = Series::gen_on_grid(&mut rng, &tgrid, width, height / 2.0);
-    render_series_to_top(ctx, &series, theme)?;
+    let params = opts.params();
+    let nx: i32 = params.int(&mut rng, &NX)?;
+    let ny: i32 = params.int(&mut rng, &NY)?;
+    let tgrid = Grid::new(Rect::from_wh(width, height / 2.0), nx as usize, ny as usize);
+    let stroke = params.float(&mut rng, &STROKE)?;
+    let series = Series::gen_on_grid(&mut rng, &tgrid);
+    render_series_to_top(ctx, &series, theme, stroke)?;
 
     let bgrid = tgrid;
-    let series = Series::gen_on_grid(&mut rng, &bgrid, width, height / 2.0);
+    let series = Series::gen_on_grid(&mut rng, &bgrid);
     ctx.save()?;
     ctx.translate(0.0, height / 2.0);
-    render_series_to_bottom(ctx, &series, theme)?;
+    render_series_to_bottom(ctx, &series, theme, stroke)?;
     ctx.restore()?;
 
     if args.show_grid {
         let cy = height / 2.0;
 
-        if bg.luminance() > 0.5 {
+        if Tone::of(&bg) == Tone::Light {
             Color::from_rgba(0x00, 0x00, 0x00, 0.6)
         } else {
             Color::from_rgba(0xff, 0xff, 0xff, 0.6)
@@ -314,10 +214,10 @@ pub fn render(opts: &dyn RenderOpts, ctx: &Context, args: &Args) -> Result<(), B
         ctx.set_line_width(1.0);
         ctx.stroke()?;
 
-        tgrid.render(ctx, width, cy)?;
+        tgrid.render(ctx)?;
 
         ctx.translate(0.0, cy);
-        bgrid.render(ctx, width, cy)?;
+        bgrid.render(ctx)?;
         ctx.restore()?;
     }
 
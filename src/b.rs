@@ -1,10 +1,19 @@
-use super::{Color, Rect, RenderOpts};
-use cairo::{Context, LineCap, RadialGradient};
+use super::{
+    error::SketchError, gradient::Gradient, params::Param, sketch::Sketch, Color, Point, Rect,
+    RenderOpts, Tone,
+};
+use cairo::{Context, LineCap};
+use clap::ArgMatches;
 use rand::Rng;
-use std::{error::Error, f64::consts::PI};
+use std::f64::consts::PI;
 
 const TAU: f64 = 2.0 * PI;
 
+const POINTS: Param = Param::int("points", 4..10).with_min(1.0);
+const LINE_WIDTH: Param = Param::float("line-width", 2.0..6.0).with_min(0.0);
+
+const PARAMS: &[Param] = &[POINTS, LINE_WIDTH];
+
 fn burst_path(ctx: &Context, ro: f64, ri: f64, n: usize) {
     let dt = TAU / n as f64;
     let ot = TAU / 4.0;
@@ -14,8 +23,10 @@ fn burst_path(ctx: &Context, ro: f64, ri: f64, n: usize) {
     for i in 1..=n {
         let tb = dt * i as f64 - ot;
         let ta = tb - dt / 2.0;
-        ctx.line_to(ro * ta.cos(), ro * ta.sin());
-        ctx.line_to(ri * tb.cos(), ri * tb.sin());
+        let pa = Point::from_polar(ro, ta);
+        let pb = Point::from_polar(ri, tb);
+        ctx.line_to(pa.x(), pa.y());
+        ctx.line_to(pb.x(), pb.y());
     }
     ctx.close_path();
 }
@@ -26,29 +37,17 @@ fn tendrils_path(ctx: &Context, ri: f64, ro: f64, y_spacing: f64, bounds: &Rect,
     let nh = n / 2;
     for i in 0..=nh {
         let t = dt * i as f64 - ot;
-        ctx.move_to(ri * t.cos(), ri * t.sin());
+        let (pi, pc) = (Point::from_polar(ri, t), Point::from_polar(1.5 * ro, t));
+        ctx.move_to(pi.x(), pi.y());
         let y = -(nh as f64) * y_spacing / 2.0 + y_spacing * i as f64;
-        ctx.curve_to(
-            1.5 * ro * t.cos(),
-            1.5 * ro * t.sin(),
-            bounds.right() - ro,
-            y,
-            bounds.right(),
-            y,
-        );
+        ctx.curve_to(pc.x(), pc.y(), bounds.right() - ro, y, bounds.right(), y);
     }
     for i in 0..=nh {
         let t = 0.75 * TAU - dt * i as f64;
-        ctx.move_to(ri * t.cos(), ri * t.sin());
+        let (pi, pc) = (Point::from_polar(ri, t), Point::from_polar(1.5 * ro, t));
+        ctx.move_to(pi.x(), pi.y());
         let y = y_spacing * i as f64 - nh as f64 * y_spacing / 2.0;
-        ctx.curve_to(
-            1.5 * ro * t.cos(),
-            1.5 * ro * t.sin(),
-            bounds.x() + ro,
-            y,
-            bounds.x(),
-            y,
-        );
+        ctx.curve_to(pc.x(), pc.y(), bounds.x() + ro, y, bounds.x(), y);
     }
 }
 
@@ -58,18 +57,43 @@ fn render_bg(
     cb: &Color,
     r: f64,
     bounds: &Rect,
-) -> Result<(), Box<dyn Error>> {
-    let g = RadialGradient::new(0.0, 0.0, 0.0, 0.0, 0.0, r);
-    g.add_color_stop_rgb(0.0, ca.r_f64(), ca.g_f64(), ca.b_f64());
-    g.add_color_stop_rgb(1.0, cb.r_f64(), cb.g_f64(), cb.b_f64());
-    ctx.set_source(g)?;
+) -> Result<(), SketchError> {
+    Gradient::radial(0.0, 0.0, 0.0, r)
+        .with_stop(0.0, *ca)
+        .with_stop(1.0, *cb)
+        .set(ctx)?;
     ctx.new_path();
     ctx.rectangle(bounds.x(), bounds.y(), bounds.width(), bounds.height());
     ctx.fill()?;
     Ok(())
 }
 
-pub fn render(opts: &dyn RenderOpts, ctx: &Context) -> Result<(), Box<dyn Error>> {
+pub struct B;
+
+impl Sketch for B {
+    fn name(&self) -> &'static str {
+        "b"
+    }
+
+    fn description(&self) -> &'static str {
+        "A starburst with tendrils over a radial gradient"
+    }
+
+    fn params(&self) -> &'static [Param] {
+        PARAMS
+    }
+
+    fn render(
+        &self,
+        opts: &dyn RenderOpts,
+        ctx: &Context,
+        _: &ArgMatches,
+    ) -> Result<(), SketchError> {
+        render(opts, ctx)
+    }
+}
+
+pub fn render(opts: &dyn RenderOpts, ctx: &Context) -> Result<(), SketchError> {
     let size = opts.size();
     let width = size.width() as f64;
     let height = size.height() as f64;
@@ -88,17 +112,18 @@ pub fn render(opts: &dyn RenderOpts, ctx: &Context) -> Result<(), Box<dyn Error>
 
     ctx.save()?;
     let ca = &theme[0];
-    let cb = if ca.luminance() > 0.5 {
-        ca.darker(1.0)
+    let cb = if Tone::of(ca) == Tone::Light {
+        ca.darken(0.15)
     } else {
-        ca.brighter(1.5)
+        ca.lighten(0.25)
     };
     ctx.translate(cx, cy);
     render_bg(ctx, ca, &cb, cx.max(cy), &Rect::from_ltrb(-cx, -cy, cx, cy))?;
     ctx.restore()?;
 
     let rt = rng.gen_range(r * 0.02..r * 0.1);
-    let n = 2 * rng.gen_range(4..10);
+    let params = opts.params();
+    let n = 2 * params.int::<usize>(&mut rng, &POINTS)?;
 
     ctx.save()?;
     ctx.translate(cx, cy);
@@ -120,7 +145,7 @@ pub fn render(opts: &dyn RenderOpts, ctx: &Context) -> Result<(), Box<dyn Error>
     }
     ctx.restore()?;
 
-    let lw = rng.gen_range(2.0..6.0);
+    let lw = params.float(&mut rng, &LINE_WIDTH)?;
     ctx.save()?;
     ctx.translate(cx, cy);
     let y_spacing = rng.gen_range(10.0..(height / 8.0));
@@ -148,7 +173,8 @@ pub fn render(opts: &dyn RenderOpts, ctx: &Context) -> Result<(), Box<dyn Error>
     for i in 0..n {
         let t = dt * i as f64 - t0;
         ctx.new_path();
-        ctx.arc(ra * t.cos(), ra * t.sin(), rb, 0.0, TAU);
+        let p = Point::from_polar(ra, t);
+        ctx.arc(p.x(), p.y(), rb, 0.0, TAU);
         theme[1].set(ctx);
         ctx.fill_preserve()?;
         theme[3].set(ctx);
@@ -0,0 +1,86 @@
+use crate::{error::SketchError, metadata::Metadata, Color, Size, ThemeChoice, Themes};
+
+mod draw;
+mod drawsome;
+mod job;
+mod themes;
+
+pub use draw::draw;
+pub use drawsome::drawsome;
+pub use themes::themes;
+
+/// The colors given with `--colors`, which stand in for a theme.
+#[derive(Debug, Clone)]
+struct Colors(Vec<Color>);
+
+impl Colors {
+    fn from_arg(s: &str) -> Result<Colors, String> {
+        Color::parse_list(s).map(Colors)
+    }
+}
+
+// The themes to render with. Colors given on the command line make up the only
+// theme, so the themes file is not needed and may not exist.
+fn open_themes(path: &str, choice: &ThemeChoice) -> Result<Themes, SketchError> {
+    if choice.colors().is_empty() {
+        Themes::open(path)
+    } else {
+        Themes::from_colors([choice.colors().to_vec()])
+    }
+}
+
+// The `draw` command line that renders the image described by `meta` again at
+// `size`, with `options` given ahead of the ones that pin its theme and params.
+fn replay_args(
+    meta: &Metadata,
+    size: Size,
+    options: impl IntoIterator<Item = String>,
+) -> Vec<String> {
+    let mut params = vec![
+        String::from("draw"),
+        format!("--seed={}", meta.seed()),
+        format!("--size={}", size),
+        format!("--themes={}", meta.themes()),
+    ];
+    params.extend(options);
+    // pinning the theme reproduces the image however the theme was chosen.
+    if let Some(theme) = meta.theme() {
+        params.push(format!("--theme={}", theme));
+    }
+    if !meta.colors().is_empty() {
+        params.push(format!("--colors={}", Color::format_list(meta.colors())));
+    }
+    for (name, value) in meta.params() {
+        params.push(format!("--param={}={}", name, value));
+    }
+    params.push(meta.sketch().to_owned());
+    params.extend(meta.args().iter().cloned());
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_need_no_themes_file() {
+        let Colors(colors) = Colors::from_arg("#112233,#ff0000").unwrap();
+        let choice = ThemeChoice::default().with_colors(colors).unwrap();
+        let themes = open_themes("no such themes.bin", &choice).unwrap();
+        assert_eq!(themes.len(), 1);
+        assert_eq!(
+            themes
+                .get(0)
+                .unwrap()
+                .iter()
+                .map(Color::to_rgb_u32)
+                .collect::<Vec<_>>(),
+            choice
+                .colors()
+                .iter()
+                .map(Color::to_rgb_u32)
+                .collect::<Vec<_>>()
+        );
+        assert!(open_themes("no such themes.bin", &ThemeChoice::default()).is_err());
+    }
+}
@@ -0,0 +1,430 @@
+use super::{
+    job::{Job, Settings},
+    Colors,
+};
+use crate::{
+    common::{Format, Seed},
+    error::SketchError,
+    metadata::Metadata,
+    params::Params,
+    sketch::Registry,
+    Color, RenderOpts, Size, ThemeChoice, ThemeFilter, Themes,
+};
+use clap::{parser::ValueSource, ArgMatches, Args as _, CommandFactory, FromArgMatches, Parser};
+use recipe::Recipe;
+use std::{
+    error::Error,
+    ops::Range,
+    path::{Path, PathBuf},
+    process,
+};
+
+mod recipe;
+mod swatches;
+
+#[derive(Parser, Debug)]
+#[command(name = "draw")]
+pub struct Args {
+    #[arg(long, default_value_t=Default::default(), value_parser=Seed::from_arg)]
+    seed: Seed,
+
+    #[arg(long, default_value_t=Size::new(1600, 600), value_parser=Size::from_arg)]
+    size: Size,
+
+    #[arg(long, default_value_t=String::from("themes.bin"))]
+    themes: String,
+
+    #[arg(long, value_enum, default_value_t=Format::Png)]
+    format: Format,
+
+    #[arg(long, default_value_t=String::from("{name}.{extension}"))]
+    dest: String,
+
+    #[arg(long, default_value_t = false)]
+    silent: bool,
+
+    #[arg(long, default_value_t = false)]
+    open: bool,
+
+    /// Override a sketch param, e.g. --param nx=32
+    #[arg(long = "param", value_parser = Params::parse_override)]
+    overrides: Vec<(String, String)>,
+
+    /// Always use the theme at this index
+    #[arg(long, conflicts_with_all = ["theme_seed", "theme_filters", "colors"])]
+    theme: Option<usize>,
+
+    /// Pick the theme with this seed rather than the render's seed, so the
+    /// theme can change while the composition stays the same
+    #[arg(long, value_parser = Seed::from_arg)]
+    theme_seed: Option<Seed>,
+
+    /// Only pick themes that match, e.g. background=dark or min-contrast=3
+    #[arg(long = "theme-filter")]
+    theme_filters: Vec<ThemeFilter>,
+
+    /// Use these colors instead of a theme, e.g. "#112233,#445566", where the
+    /// first is the background and missing colors are derived from the others
+    #[arg(long, value_parser = Colors::from_arg, conflicts_with_all = ["theme_seed", "theme_filters"])]
+    colors: Option<Colors>,
+
+    /// Render the settings saved in a recipe file instead of a sketch
+    #[arg(long)]
+    recipe: Option<PathBuf>,
+
+    /// Save the settings of this render as a recipe file (TOML, or JSON for .json)
+    #[arg(long)]
+    save_recipe: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct Replay {
+    src: PathBuf,
+
+    #[arg(long, value_parser=Size::from_arg)]
+    size: Option<Size>,
+
+    /// The format to write, defaults to the format of the image being replayed
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+
+    #[arg(long)]
+    dest: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct Swatches {
+    /// Only show the themes with indices in a range, e.g. 10..20
+    #[arg(long, value_parser = swatches::parse_range)]
+    range: Option<Range<usize>>,
+
+    /// The number of columns of themes in a PNG or SVG
+    #[arg(long, default_value_t = 2)]
+    columns: usize,
+
+    /// Where to write the swatches, defaults to themes.<format>
+    #[arg(long)]
+    dest: Option<PathBuf>,
+}
+
+impl Args {
+    fn settings(&self) -> Result<Settings, SketchError> {
+        Ok(Settings {
+            seed: self.seed,
+            size: self.size,
+            themes: self.themes.clone(),
+            format: self.format,
+            dest: self.dest.clone(),
+            overrides: self.overrides.clone(),
+            theme: self.theme_choice()?,
+        })
+    }
+
+    fn theme_choice(&self) -> Result<ThemeChoice, SketchError> {
+        let choice = ThemeChoice::new(self.theme, self.theme_seed, self.theme_filters.clone());
+        match &self.colors {
+            Some(Colors(colors)) => choice.with_colors(colors.clone()),
+            None => Ok(choice),
+        }
+    }
+}
+
+fn open_image<P: AsRef<Path>>(src: P) -> Result<(), Box<dyn Error>> {
+    process::Command::new("open")
+        .args(&[format!("{}", src.as_ref().display())])
+        .spawn()?;
+    Ok(())
+}
+
+// The options that control how draw behaves rather than what it renders, and
+// which carry over when the command line is rebuilt from a replay or recipe.
+fn passthrough(args: &Args) -> Vec<String> {
+    let mut params = Vec::new();
+    if args.silent {
+        params.push(String::from("--silent"));
+    }
+    if args.open {
+        params.push(String::from("--open"));
+    }
+    if let Some(dst) = &args.save_recipe {
+        params.push(format!("--save-recipe={}", dst.display()));
+    }
+    params
+}
+
+// Rebuilds the command line that produced the image at `replay.src`, so that a
+// replay goes through exactly the same path as a render.
+fn replay(args: &Args, replay: &Replay) -> Result<Vec<String>, Box<dyn Error>> {
+    let (meta, format) = Metadata::read_with_format(&replay.src)?;
+    let mut options = vec![
+        format!("--format={}", replay.format.unwrap_or(format)),
+        format!("--dest={}", replay.dest.as_ref().unwrap_or(&args.dest)),
+    ];
+    options.extend(passthrough(args));
+    Ok(super::replay_args(
+        &meta,
+        replay.size.unwrap_or_else(|| meta.size()),
+        options,
+    ))
+}
+
+// Rebuilds the command line from a recipe. Options that were given explicitly
+// on the command line take precedence over the ones in the recipe.
+fn from_recipe(
+    args: &Args,
+    matches: &ArgMatches,
+    src: &Path,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let recipe = Recipe::load(src)?;
+    let pick = |id: &str, arg: String, saved: &str| {
+        if matches.value_source(id) == Some(ValueSource::CommandLine) {
+            arg
+        } else {
+            saved.to_owned()
+        }
+    };
+    let mut params = vec![
+        String::from("draw"),
+        format!(
+            "--seed={}",
+            pick("seed", format!("{}", args.seed), recipe.seed())
+        ),
+        format!(
+            "--size={}",
+            pick("size", format!("{}", args.size), recipe.size())
+        ),
+        format!(
+            "--themes={}",
+            pick("themes", args.themes.clone(), recipe.themes())
+        ),
+        format!(
+            "--format={}",
+            pick("format", format!("{}", args.format), recipe.format())
+        ),
+        format!("--dest={}", pick("dest", args.dest.clone(), recipe.dest())),
+    ];
+    params.extend(passthrough(args));
+    let theme_given = ["theme", "theme_seed", "theme_filters", "colors"]
+        .iter()
+        .any(|id| matches.value_source(id) == Some(ValueSource::CommandLine));
+    if theme_given {
+        params.extend(theme_args(&args.theme_choice()?));
+    } else {
+        params.extend(recipe.theme_args());
+    }
+    for (name, value) in recipe.params() {
+        params.push(format!("--param={}={}", name, value));
+    }
+    for (name, value) in args.overrides.iter() {
+        params.push(format!("--param={}={}", name, value));
+    }
+    params.push(recipe.sketch().to_owned());
+    params.extend(recipe.args().iter().cloned());
+    Ok(params)
+}
+
+// Renders the themes file as swatches rather than rendering a sketch.
+fn swatches(args: &Args, swatches: &Swatches) -> Result<(), Box<dyn Error>> {
+    let themes = Themes::open(&args.themes)?;
+    let dst = swatches
+        .dest
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("themes.{}", args.format.extension())));
+    let (count, written) = swatches::render(
+        &themes,
+        swatches.range.clone().unwrap_or(0..usize::MAX),
+        swatches.columns,
+        args.format,
+        &dst,
+    )?;
+    if !args.silent {
+        let names = written
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>();
+        println!("rendered {} themes to {}", count, names.join(", "));
+    }
+    if args.open {
+        for dst in written.iter() {
+            open_image(dst)?;
+        }
+    }
+    Ok(())
+}
+
+// The options that reproduce a theme choice.
+fn theme_args(choice: &ThemeChoice) -> Vec<String> {
+    let mut params = Vec::new();
+    if let Some(index) = choice.index() {
+        params.push(format!("--theme={}", index));
+    }
+    if let Some(seed) = choice.seed() {
+        params.push(format!("--theme-seed={}", seed));
+    }
+    for filter in choice.filters() {
+        params.push(format!("--theme-filter={}", filter));
+    }
+    if !choice.colors().is_empty() {
+        params.push(format!("--colors={}", Color::format_list(choice.colors())));
+    }
+    params
+}
+
+fn command(registry: &Registry) -> clap::Command {
+    let replay = clap::Command::new("replay")
+        .about("Render an image again from the metadata embedded in it");
+    let swatches = clap::Command::new("themes")
+        .about("Render the themes in the themes file as labeled swatches");
+    registry.iter().fold(
+        Args::command()
+            .subcommand(Replay::augment_args(replay))
+            .subcommand(Swatches::augment_args(swatches)),
+        |cmd, sketch| cmd.subcommand(sketch.command()),
+    )
+}
+
+fn job<'a>(
+    registry: &'a Registry,
+    args: &Args,
+    matches: &ArgMatches,
+) -> Result<Job<'a>, Box<dyn Error>> {
+    let (name, matches) = matches
+        .subcommand()
+        .ok_or("no sketch given, either name one or use --recipe")?;
+    let sketch = registry
+        .get(name)
+        .ok_or_else(|| format!("unknown sketch: {}", name))?;
+    let settings = args.settings()?;
+    let themes = super::open_themes(&args.themes, &settings.theme)?;
+    Ok(Job::new(settings, sketch, matches.clone(), themes))
+}
+
+/// Runs the `draw` command line tool with the sketches in `registry`.
+pub fn draw(registry: &Registry) -> Result<(), Box<dyn Error>> {
+    let matches = command(registry).get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let params = match (&args.recipe, matches.subcommand()) {
+        (Some(_), Some(_)) => {
+            return Err("--recipe cannot be combined with a sketch or replay".into())
+        }
+        (None, Some(("themes", matches))) => {
+            return swatches(&args, &Swatches::from_arg_matches(matches)?)
+        }
+        (Some(src), None) => Some(from_recipe(&args, &matches, src)?),
+        (None, Some(("replay", matches))) => {
+            Some(replay(&args, &Replay::from_arg_matches(matches)?)?)
+        }
+        _ => None,
+    };
+    let matches = match params {
+        Some(params) => command(registry).try_get_matches_from(params)?,
+        None => matches,
+    };
+
+    let args = Args::from_arg_matches(&matches)?;
+    let job = job(registry, &args, &matches)?;
+    if let Some(dst) = &args.save_recipe {
+        Recipe::from_job(&job).save(dst)?;
+    }
+    if !args.silent {
+        println!("seed: {}", args.seed);
+    }
+    let dst = job.render()?;
+    if !args.silent {
+        let params = job.params().resolved();
+        if !params.is_empty() {
+            println!(
+                "params: {}",
+                params
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+        }
+    }
+    if args.open {
+        open_image(&dst)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_line_wins_over_recipe() {
+        let src = std::env::temp_dir().join(format!("sketches-{}-wins.toml", std::process::id()));
+        std::fs::write(
+            &src,
+            r#"
+            seed = "00000001"
+            size = "100x100"
+            themes = "saved.bin"
+            format = "pdf"
+            dest = "saved.{extension}"
+            sketch = "b"
+            theme = 4
+            args = ["--mode=dots"]
+
+            [params]
+            points = "30"
+            "#,
+        )
+        .unwrap();
+        let registry = Registry::new();
+        let params = |cli: &[&str]| {
+            let matches = command(&registry)
+                .try_get_matches_from(
+                    ["draw", "--recipe", src.to_str().unwrap()]
+                        .iter()
+                        .chain(cli.iter()),
+                )
+                .unwrap();
+            let args = Args::from_arg_matches(&matches).unwrap();
+            from_recipe(&args, &matches, &src).unwrap()
+        };
+
+        assert_eq!(
+            params(&[]),
+            vec![
+                "draw",
+                "--seed=00000001",
+                "--size=100x100",
+                "--themes=saved.bin",
+                "--format=pdf",
+                "--dest=saved.{extension}",
+                "--theme=4",
+                "--param=points=30",
+                "b",
+                "--mode=dots",
+            ]
+        );
+        let given = params(&[
+            "--seed=ff",
+            "--format=svg",
+            "--colors=#1d2b53,#ff004d,#ffa300,#29adff,#00e436",
+            "--param=points=5",
+            "--silent",
+        ]);
+        std::fs::remove_file(&src).unwrap();
+        assert_eq!(
+            given,
+            vec![
+                "draw",
+                "--seed=000000ff",
+                "--size=100x100",
+                "--themes=saved.bin",
+                "--format=svg",
+                "--dest=saved.{extension}",
+                "--silent",
+                "--colors=#1d2b53,#ff004d,#ffa300,#29adff,#00e436",
+                "--param=points=30",
+                "--param=points=5",
+                "b",
+                "--mode=dots",
+            ]
+        );
+    }
+}
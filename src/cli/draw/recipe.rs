@@ -0,0 +1,253 @@
+use super::Job;
+use crate::Color;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, error::Error, fmt::Display, fs, path::Path};
+
+/// The complete settings for a render, saved as TOML, or as JSON when the file
+/// has a `.json` extension.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Recipe {
+    seed: String,
+    size: String,
+    themes: String,
+    format: String,
+    dest: String,
+    sketch: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    theme: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    theme_seed: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    theme_filters: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    colors: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    args: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    params: BTreeMap<String, Value>,
+}
+
+// Params are stored as strings, but numbers are accepted as well since that is
+// what people tend to write when editing a recipe by hand.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Value {
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{}", v),
+            Value::Text(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+fn is_json<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("json"))
+        .unwrap_or(false)
+}
+
+impl Recipe {
+    pub fn from_job(job: &Job) -> Self {
+        let settings = job.settings();
+        Self {
+            seed: format!("{}", settings.seed),
+            size: format!("{}", settings.size),
+            themes: settings.themes.clone(),
+            format: format!("{}", settings.format),
+            dest: settings.dest.clone(),
+            sketch: job.sketch().name().to_owned(),
+            theme: settings.theme.index(),
+            theme_seed: settings.theme.seed().map(|s| format!("{}", s)),
+            theme_filters: settings
+                .theme
+                .filters()
+                .iter()
+                .map(|f| format!("{}", f))
+                .collect(),
+            colors: Some(settings.theme.colors())
+                .filter(|c| !c.is_empty())
+                .map(Color::format_list),
+            args: job.sketch_args(),
+            params: settings
+                .overrides
+                .iter()
+                .map(|(k, v)| (k.clone(), Value::Text(v.clone())))
+                .collect(),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(src: P) -> Result<Self, Box<dyn Error>> {
+        let data = fs::read_to_string(&src)?;
+        if is_json(&src) {
+            Ok(serde_json::from_str(&data)?)
+        } else {
+            Ok(toml::from_str(&data)?)
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, dst: P) -> Result<(), Box<dyn Error>> {
+        let data = if is_json(&dst) {
+            serde_json::to_string_pretty(self)?
+        } else {
+            toml::to_string(self)?
+        };
+        fs::write(dst, data)?;
+        Ok(())
+    }
+
+    pub fn seed(&self) -> &str {
+        &self.seed
+    }
+
+    pub fn size(&self) -> &str {
+        &self.size
+    }
+
+    pub fn themes(&self) -> &str {
+        &self.themes
+    }
+
+    pub fn format(&self) -> &str {
+        &self.format
+    }
+
+    pub fn dest(&self) -> &str {
+        &self.dest
+    }
+
+    pub fn sketch(&self) -> &str {
+        &self.sketch
+    }
+
+    /// The `draw` options for the recipe's theme choice.
+    pub fn theme_args(&self) -> Vec<String> {
+        let mut params = Vec::new();
+        if let Some(index) = self.theme {
+            params.push(format!("--theme={}", index));
+        }
+        if let Some(seed) = &self.theme_seed {
+            params.push(format!("--theme-seed={}", seed));
+        }
+        for filter in self.theme_filters.iter() {
+            params.push(format!("--theme-filter={}", filter));
+        }
+        if let Some(colors) = &self.colors {
+            params.push(format!("--colors={}", colors));
+        }
+        params
+    }
+
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    pub fn params(&self) -> impl Iterator<Item = (&str, String)> {
+        self.params
+            .iter()
+            .map(|(k, v)| (k.as_str(), format!("{}", v)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipe() -> Recipe {
+        Recipe {
+            seed: String::from("00c0ffee"),
+            size: String::from("320x200"),
+            themes: String::from("themes.bin"),
+            format: String::from("svg"),
+            dest: String::from("out/{name}.{extension}"),
+            sketch: String::from("a"),
+            theme: None,
+            theme_seed: Some(String::from("0000beef")),
+            theme_filters: vec![String::from("background=dark")],
+            colors: Some(String::from("#112233,#ff0000")),
+            args: vec![String::from("--mode=lines")],
+            params: [
+                (String::from("nx"), Value::Text(String::from("12"))),
+                (String::from("stroke"), Value::Text(String::from("1.5"))),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    fn round_trip(name: &str) -> Recipe {
+        let dst = std::env::temp_dir().join(format!("sketches-{}-{}", std::process::id(), name));
+        recipe().save(&dst).unwrap();
+        let loaded = Recipe::load(&dst).unwrap();
+        fs::remove_file(&dst).unwrap();
+        loaded
+    }
+
+    fn assert_same(a: &Recipe, b: &Recipe) {
+        assert_eq!(a.seed(), b.seed());
+        assert_eq!(a.size(), b.size());
+        assert_eq!(a.themes(), b.themes());
+        assert_eq!(a.format(), b.format());
+        assert_eq!(a.dest(), b.dest());
+        assert_eq!(a.sketch(), b.sketch());
+        assert_eq!(a.theme_args(), b.theme_args());
+        assert_eq!(a.args(), b.args());
+        assert_eq!(
+            a.params().collect::<Vec<_>>(),
+            b.params().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        assert_same(&recipe(), &round_trip("recipe.toml"));
+    }
+
+    #[test]
+    fn json_round_trip() {
+        assert_same(&recipe(), &round_trip("recipe.JSON"));
+    }
+
+    #[test]
+    fn hand_written() {
+        let recipe: Recipe = toml::from_str(
+            r#"
+            seed = "1"
+            size = "100x100"
+            themes = "themes.bin"
+            format = "png"
+            dest = "{name}.{extension}"
+            sketch = "b"
+            theme = 4
+
+            [params]
+            points = 30
+            line_width = 0.5
+            "#,
+        )
+        .unwrap();
+        assert_eq!(recipe.theme_args(), vec!["--theme=4"]);
+        assert!(recipe.args().is_empty());
+        assert_eq!(
+            recipe.params().collect::<Vec<_>>(),
+            vec![
+                ("line_width", String::from("0.5")),
+                ("points", String::from("30"))
+            ]
+        );
+        assert!(toml::from_str::<Recipe>("seed = \"1\"").is_err());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{common::Format, Color, Themes, Tone};
+use cairo::{Context, FontSlant, FontWeight, ImageSurface, PdfSurface, SvgSurface};
+use std::{
+    error::Error,
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+const MARGIN: f64 = 24.0;
+const GAP: f64 = 12.0;
+const LABEL: f64 = 48.0;
+const SWATCH_WIDTH: f64 = 140.0;
+const SWATCH_HEIGHT: f64 = 56.0;
+const STRIPS_PER_PAGE: usize = 12;
+// The largest width or height of an image surface that cairo can create.
+const MAX_IMAGE_SIZE: f64 = 32767.0;
+
+/// Parses an index range such as `10..20`, `10..`, `..20` or a single `7`.
+pub fn parse_range(s: &str) -> Result<Range<usize>, String> {
+    let parse = |v: &str, default: usize| {
+        if v.is_empty() {
+            Ok(default)
+        } else {
+            v.parse::<usize>()
+                .map_err(|_| format!("invalid range: {}, expected lo..hi", s))
+        }
+    };
+    match s.find("..") {
+        Some(ix) => Ok(parse(&s[..ix], 0)?..parse(&s[ix + 2..], usize::MAX)?),
+        None => {
+            let ix = parse(s, 0)?;
+            Ok(ix..ix + 1)
+        }
+    }
+}
+
+fn strip_width(themes: &Themes) -> f64 {
+    LABEL + themes.get(0).map_or(0, |t| t.len()) as f64 * SWATCH_WIDTH
+}
+
+// Draws one theme with its index, and the hex code and luminance of each color
+// in text that contrasts with it.
+fn draw_strip(
+    ctx: &Context,
+    x: f64,
+    y: f64,
+    ix: usize,
+    colors: &[Color],
+) -> Result<(), Box<dyn Error>> {
+    ctx.set_font_size(12.0);
+    Color::black().set(ctx);
+    ctx.move_to(x, y + SWATCH_HEIGHT / 2.0 + 4.0);
+    ctx.show_text(&format!("{}", ix))?;
+
+    for (i, color) in colors.iter().enumerate() {
+        let sx = x + LABEL + i as f64 * SWATCH_WIDTH;
+        color.set(ctx);
+        ctx.rectangle(sx, y, SWATCH_WIDTH, SWATCH_HEIGHT);
+        ctx.fill()?;
+
+        let lum = color.relative_luminance();
+        if Tone::of(color) == Tone::Light {
+            Color::black().set(ctx);
+        } else {
+            Color::white().set(ctx);
+        }
+        ctx.set_font_size(11.0);
+        ctx.move_to(sx + 8.0, y + SWATCH_HEIGHT - 26.0);
+        ctx.show_text(&format!("{}", color))?;
+        ctx.set_font_size(9.0);
+        ctx.move_to(sx + 8.0, y + SWATCH_HEIGHT - 10.0);
+        ctx.show_text(&format!("L {:0.2}", lum))?;
+    }
+    Ok(())
+}
+
+// How many strips or rows of strips of `size` fit within `extent` once the
+// margins are taken off.
+fn fit(extent: f64, size: f64) -> usize {
+    (((extent - 2.0 * MARGIN + GAP) / (size + GAP)) as usize).max(1)
+}
+
+// `dst` with `-n` added to the end of its file stem.
+fn numbered(dst: &Path, n: usize) -> PathBuf {
+    let stem = dst
+        .file_stem()
+        .map_or_else(Default::default, |s| s.to_string_lossy());
+    match dst.extension() {
+        Some(ext) => dst.with_file_name(format!("{}-{}.{}", stem, n, ext.to_string_lossy())),
+        None => dst.with_file_name(format!("{}-{}", stem, n)),
+    }
+}
+
+fn draw_grid(
+    ctx: &Context,
+    themes: &Themes,
+    indices: &[usize],
+    columns: usize,
+) -> Result<(), Box<dyn Error>> {
+    let width = strip_width(themes);
+    for (n, &ix) in indices.iter().enumerate() {
+        let x = MARGIN + (n % columns) as f64 * (width + GAP);
+        let y = MARGIN + (n / columns) as f64 * (SWATCH_HEIGHT + GAP);
+        let colors = themes.get(ix).ok_or_else(|| format!("no theme {}", ix))?;
+        draw_strip(ctx, x, y, ix, &colors)?;
+    }
+    Ok(())
+}
+
+/// Renders the themes whose indices are in `range` as labeled swatch strips,
+/// and returns how many there were and the files they were written to. PNG and
+/// SVG get a grid with `columns` columns, while PDF gets a page for every few
+/// themes. A PNG that would be too large for cairo is split into files numbered
+/// from `dst`.
+pub fn render(
+    themes: &Themes,
+    range: Range<usize>,
+    columns: usize,
+    format: Format,
+    dst: &Path,
+) -> Result<(usize, Vec<PathBuf>), Box<dyn Error>> {
+    let indices = (range.start..range.end.min(themes.len())).collect::<Vec<_>>();
+    if indices.is_empty() {
+        return Err(format!(
+            "no themes from index {}, there are {}",
+            range.start,
+            themes.len()
+        )
+        .into());
+    }
+
+    let columns = match format {
+        Format::Pdf => 1,
+        Format::Png => columns.clamp(
+            1,
+            indices.len().min(fit(MAX_IMAGE_SIZE, strip_width(themes))),
+        ),
+        Format::Svg => columns.clamp(1, indices.len()),
+    };
+    let size = |rows: usize| {
+        (
+            2.0 * MARGIN + columns as f64 * (strip_width(themes) + GAP) - GAP,
+            2.0 * MARGIN + rows as f64 * (SWATCH_HEIGHT + GAP) - GAP,
+        )
+    };
+
+    let setup = |ctx: &Context| {
+        ctx.select_font_face("sans-serif", FontSlant::Normal, FontWeight::Normal);
+        Color::white().set(ctx);
+        ctx.paint()
+    };
+    let written = match format {
+        Format::Pdf => {
+            let (width, height) = size(indices.len().min(STRIPS_PER_PAGE));
+            let surface = PdfSurface::new(width, height, dst)?;
+            let ctx = Context::new(&surface)?;
+            for page in indices.chunks(STRIPS_PER_PAGE) {
+                setup(&ctx)?;
+                draw_grid(&ctx, themes, page, 1)?;
+                ctx.show_page()?;
+            }
+            surface.finish();
+            vec![dst.to_path_buf()]
+        }
+        Format::Png => {
+            let per_image = fit(MAX_IMAGE_SIZE, SWATCH_HEIGHT) * columns;
+            let images = indices.chunks(per_image).collect::<Vec<_>>();
+            let mut written = Vec::with_capacity(images.len());
+            for (n, image) in images.iter().enumerate() {
+                let dst = match images.len() {
+                    1 => dst.to_path_buf(),
+                    _ => numbered(dst, n + 1),
+                };
+                let (width, height) = size(image.len().div_ceil(columns));
+                let surface = ImageSurface::create(
+                    cairo::Format::ARgb32,
+                    width.ceil() as i32,
+                    height.ceil() as i32,
+                )?;
+                let ctx = Context::new(&surface)?;
+                setup(&ctx)?;
+                draw_grid(&ctx, themes, image, columns)?;
+                surface.write_to_png(&mut fs::File::create(&dst)?)?;
+                written.push(dst);
+            }
+            written
+        }
+        Format::Svg => {
+            let (width, height) = size(indices.len().div_ceil(columns));
+            let surface = SvgSurface::new(width, height, Some(dst))?;
+            let ctx = Context::new(&surface)?;
+            setup(&ctx)?;
+            draw_grid(&ctx, themes, &indices, columns)?;
+            surface.finish();
+            vec![dst.to_path_buf()]
+        }
+    };
+    Ok((indices.len(), written))
+}
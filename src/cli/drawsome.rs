@@ -0,0 +1,237 @@
+use super::{
+    job::{Job, Settings},
+    Colors,
+};
+use crate::{
+    common::{Format, Seed},
+    error::SketchError,
+    params::Params,
+    sketch::Registry,
+    Size, ThemeChoice, ThemeFilter,
+};
+use chrono::Utc;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use gallery::Entry;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+use std::{
+    any::Any,
+    error::Error,
+    io::{self, Write},
+    num::NonZeroUsize,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+mod gallery;
+
+#[derive(Parser, Debug)]
+#[command(name = "drawsome")]
+struct Args {
+    #[arg(long, default_value_t=Size::new(1600, 600),value_parser=Size::from_arg)]
+    size: Size,
+
+    #[arg(long, default_value_t=String::from("themes.bin"))]
+    themes: String,
+
+    #[arg(long, value_enum, default_value_t=Format::Png)]
+    format: Format,
+
+    #[arg(long, default_value_t = 10)]
+    count: usize,
+
+    #[arg(long, default_value_t=String::from("{name}/{seed}.{extension}"))]
+    dest: String,
+
+    /// Number of renders to run at once, defaults to the number of CPUs
+    #[arg(long)]
+    jobs: Option<NonZeroUsize>,
+
+    #[arg(long, default_value_t = false)]
+    silent: bool,
+
+    /// Also draw the batch into a multi-page PDF next to the gallery
+    #[arg(long, default_value_t = false)]
+    contact_sheet: bool,
+
+    /// Override a sketch param in every render, e.g. --param nx=32
+    #[arg(long = "param", value_parser = Params::parse_override)]
+    overrides: Vec<(String, String)>,
+
+    /// Always use the theme at this index
+    #[arg(long, conflicts_with_all = ["theme_seed", "theme_filters", "colors"])]
+    theme: Option<usize>,
+
+    /// Pick the theme with this seed rather than the render's seed, so the
+    /// theme can change while the composition stays the same
+    #[arg(long, value_parser = Seed::from_arg)]
+    theme_seed: Option<Seed>,
+
+    /// Only pick themes that match, e.g. background=dark or min-contrast=3
+    #[arg(long = "theme-filter")]
+    theme_filters: Vec<ThemeFilter>,
+
+    /// Use these colors instead of a theme, e.g. "#112233,#445566", where the
+    /// first is the background and missing colors are derived from the others
+    #[arg(long, value_parser = Colors::from_arg, conflicts_with_all = ["theme_seed", "theme_filters"])]
+    colors: Option<Colors>,
+}
+
+impl Args {
+    fn settings(&self, seed: Seed) -> Result<Settings, SketchError> {
+        Ok(Settings {
+            seed,
+            size: self.size,
+            themes: self.themes.clone(),
+            format: self.format,
+            dest: self.dest.clone(),
+            overrides: self.overrides.clone(),
+            theme: self.theme_choice()?,
+        })
+    }
+
+    fn theme_choice(&self) -> Result<ThemeChoice, SketchError> {
+        let choice = ThemeChoice::new(self.theme, self.theme_seed, self.theme_filters.clone());
+        match &self.colors {
+            Some(Colors(colors)) => choice.with_colors(colors.clone()),
+            None => Ok(choice),
+        }
+    }
+
+    fn jobs(&self) -> usize {
+        self.jobs
+            .or_else(|| thread::available_parallelism().ok())
+            .map(NonZeroUsize::get)
+            .unwrap_or(1)
+    }
+}
+
+fn report_progress(done: usize, failed: usize, count: usize) {
+    let mut stderr = io::stderr();
+    write!(stderr, "\rrendered {}/{}", done, count).ok();
+    if failed > 0 {
+        write!(stderr, ", {} failed", failed).ok();
+    }
+    stderr.flush().ok();
+}
+
+/// Runs the `drawsome` command line tool with the sketches in `registry`. The
+/// images are rendered in this process on a pool of worker threads.
+pub fn drawsome(registry: &Registry) -> Result<(), Box<dyn Error>> {
+    let matches = registry
+        .iter()
+        .fold(Args::command(), |cmd, sketch| {
+            cmd.subcommand(sketch.command())
+        })
+        .subcommand_required(true)
+        .get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let (name, sketch_matches) = matches.subcommand().ok_or("no sketch given")?;
+    let sketch = registry
+        .get(name)
+        .ok_or_else(|| format!("unknown sketch: {}", name))?;
+    // check the theme choice once up front rather than failing every render.
+    let choice = args.theme_choice()?;
+    let themes = super::open_themes(&args.themes, &choice)?;
+    themes.clone().choose(&choice)?;
+
+    let mut rng = Pcg64::seed_from_u64(Utc::now().timestamp() as u64);
+    let seeds = (0..args.count)
+        .map(|_| Seed::new(rng.gen::<u64>()))
+        .collect::<Vec<_>>();
+
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel::<(usize, Result<Entry, String>)>();
+    let mut entries = Vec::new();
+    let mut failures = Vec::new();
+    thread::scope(|s| {
+        for _ in 0..args.jobs().min(seeds.len()) {
+            let tx = tx.clone();
+            let (args, seeds, next, themes) = (&args, &seeds, &next, &themes);
+            s.spawn(move || {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&seed) = seeds.get(i) else { break };
+                    // a sketch that panics fails its own render rather than
+                    // taking the rest of the batch down with it.
+                    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                        args.settings(seed)
+                            .and_then(|settings| {
+                                let job = Job::new(
+                                    settings,
+                                    sketch,
+                                    sketch_matches.clone(),
+                                    themes.clone(),
+                                );
+                                job.render().map(|dst| Entry::new(dst, job.metadata()))
+                            })
+                            .map_err(|e| e.to_string())
+                    }))
+                    .unwrap_or_else(|e| Err(panic_message(e.as_ref())));
+                    if tx.send((i, res)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        for (done, (i, res)) in rx.iter().enumerate() {
+            match res {
+                Ok(entry) => entries.push((i, entry)),
+                Err(e) => failures.push((seeds[i], e)),
+            }
+            if !args.silent {
+                report_progress(done + 1, failures.len(), seeds.len());
+            }
+        }
+    });
+    if !args.silent && !seeds.is_empty() {
+        eprintln!();
+    }
+
+    // list the images in the order of their seeds rather than as they finished.
+    entries.sort_by_key(|(i, _)| *i);
+    if !entries.is_empty() {
+        let entries = entries.into_iter().map(|(_, e)| e).collect::<Vec<_>>();
+        let index = gallery::write_index(name, args.format, &entries)?;
+        if !args.silent {
+            println!("gallery: {}", index.display());
+        }
+        if args.contact_sheet {
+            let dst = index.with_file_name("contact-sheet.pdf");
+            gallery::write_contact_sheet(&dst, &entries, |meta| {
+                Ok(Job::new(
+                    args.settings(meta.seed())?,
+                    sketch,
+                    sketch_matches.clone(),
+                    themes.clone(),
+                ))
+            })?;
+            if !args.silent {
+                println!("contact sheet: {}", dst.display());
+            }
+        }
+    }
+
+    if failures.is_empty() {
+        return Ok(());
+    }
+    for (seed, e) in failures.iter() {
+        eprintln!("seed {}: {}", seed, e);
+    }
+    Err(format!("{} of {} renders failed", failures.len(), seeds.len()).into())
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    let msg = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(|s| s.as_str()))
+        .unwrap_or("unknown cause");
+    format!("the sketch panicked: {}", msg)
+}
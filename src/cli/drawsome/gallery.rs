@@ -0,0 +1,238 @@
+use super::Job;
+use crate::{common::Format, error::SketchError, metadata::Metadata, Color};
+use cairo::{Context, FontSlant, FontWeight, ImageSurface, PdfSurface};
+use serde::Serialize;
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+use tinytemplate::TinyTemplate;
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; background: #f4f4f4; color: #222; }
+main { display: grid; grid-template-columns: repeat(auto-fill, minmax(320px, 1fr)); gap: 1.5em; }
+figure { margin: 0; padding: 0.75em; background: #fff; box-shadow: 0 1px 3px rgba(0, 0, 0, 0.2); }
+figure img { width: 100%; display: block; }
+figcaption { font-size: 0.85em; margin-top: 0.5em; }
+figcaption ul { list-style: none; padding: 0; margin: 0.5em 0; color: #555; }
+figcaption div { display: flex; gap: 0.5em; }
+figcaption input { flex: 1; font-family: monospace; font-size: 0.9em; }
+";
+
+const TEMPLATE: &str = "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{title}</title>
+<style>{style | unescaped}</style>
+</head>
+<body>
+<h1>{title}</h1>
+<main>
+{{ for entry in entries }}<figure>
+{{ if entry.image }}<a href=\"{entry.href}\"><img src=\"{entry.href}\" loading=\"lazy\" alt=\"{entry.seed}\"></a>{{ else }}<a href=\"{entry.href}\">{entry.href}</a>{{ endif }}
+<figcaption>
+<strong>{entry.seed}</strong> theme {entry.theme}
+<ul>{{ for param in entry.params }}<li>{param}</li>{{ endfor }}</ul>
+<div><input type=\"text\" value=\"{entry.command}\" readonly><button onclick=\"navigator.clipboard.writeText(this.previousElementSibling.value)\">copy</button></div>
+</figcaption>
+</figure>
+{{ endfor }}</main>
+</body>
+</html>
+";
+
+/// A rendered image as it's listed in the gallery.
+pub struct Entry {
+    path: PathBuf,
+    meta: Metadata,
+}
+
+impl Entry {
+    pub fn new(path: PathBuf, meta: Metadata) -> Self {
+        Self { path, meta }
+    }
+}
+
+#[derive(Serialize)]
+struct Index {
+    title: String,
+    style: &'static str,
+    entries: Vec<Item>,
+}
+
+#[derive(Serialize)]
+struct Item {
+    href: String,
+    image: bool,
+    seed: String,
+    theme: String,
+    params: Vec<String>,
+    command: String,
+}
+
+// Quotes `s` for a POSIX shell, unless it's made of characters that are safe
+// as they are.
+fn quote(s: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_.,/:=+@%".contains(c);
+    if !s.is_empty() && s.chars().all(safe) {
+        s.to_owned()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+// The index of the theme an image used, or its colors when they were given in
+// place of a theme.
+fn theme_label(meta: &Metadata) -> Option<String> {
+    match meta.theme() {
+        Some(theme) => Some(theme.to_string()),
+        None if !meta.colors().is_empty() => Some(Color::format_list(meta.colors())),
+        None => None,
+    }
+}
+
+/// The `draw` command line that renders the image described by `meta` again,
+/// in `format`.
+pub fn command(meta: &Metadata, format: Format) -> String {
+    crate::cli::replay_args(meta, meta.size(), [format!("--format={}", format)])
+        .iter()
+        .map(|p| quote(p))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// The deepest directory that contains all of `paths`.
+fn common_dir(paths: &[PathBuf]) -> PathBuf {
+    let mut dirs = paths.iter().filter_map(|p| p.parent());
+    let first = match dirs.next() {
+        Some(dir) => dir.to_path_buf(),
+        None => return PathBuf::from("."),
+    };
+    dirs.fold(first, |acc, dir| {
+        acc.components()
+            .zip(dir.components())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a)
+            .collect()
+    })
+}
+
+/// Writes an `index.html` listing `entries`, which were rendered in `format`, to
+/// the directory that holds all of them, and returns its path.
+pub fn write_index(
+    title: &str,
+    format: Format,
+    entries: &[Entry],
+) -> Result<PathBuf, Box<dyn Error>> {
+    let paths = entries
+        .iter()
+        .map(|e| fs::canonicalize(&e.path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let dir = common_dir(&paths);
+
+    let items = entries
+        .iter()
+        .zip(paths.iter())
+        .map(|(entry, path)| {
+            let href = path.strip_prefix(&dir).unwrap_or(path);
+            let image = matches!(
+                href.extension().and_then(|e| e.to_str()),
+                Some("png") | Some("svg")
+            );
+            Item {
+                href: href.to_string_lossy().into_owned(),
+                image,
+                seed: format!("{}", entry.meta.seed()),
+                theme: theme_label(&entry.meta).unwrap_or_else(|| String::from("-")),
+                params: entry
+                    .meta
+                    .params()
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect(),
+                command: command(&entry.meta, format),
+            }
+        })
+        .collect();
+
+    let mut tt = TinyTemplate::new();
+    tt.add_formatter("unescaped", tinytemplate::format_unescaped);
+    tt.add_template("index", TEMPLATE)?;
+    let html = tt.render(
+        "index",
+        &Index {
+            title: title.to_owned(),
+            style: STYLE,
+            entries: items,
+        },
+    )?;
+
+    let dst = dir.join("index.html");
+    fs::write(&dst, html)?;
+    Ok(dst)
+}
+
+// A4 landscape, in points.
+const PAGE_WIDTH: f64 = 842.0;
+const PAGE_HEIGHT: f64 = 595.0;
+const MARGIN: f64 = 36.0;
+const GAP: f64 = 12.0;
+const CAPTION: f64 = 14.0;
+const COLUMNS: usize = 3;
+
+/// Lays `entries` out in a grid on the pages of a PDF at `dst`, with the seed
+/// and theme under each one. PNGs are placed as they were rendered, and other
+/// formats, which cairo can't read back, are drawn again with the job that
+/// `redraw` gives for their metadata.
+pub fn write_contact_sheet<'a>(
+    dst: &Path,
+    entries: &[Entry],
+    redraw: impl Fn(&Metadata) -> Result<Job<'a>, SketchError>,
+) -> Result<(), Box<dyn Error>> {
+    let surface = PdfSurface::new(PAGE_WIDTH, PAGE_HEIGHT, dst)?;
+    let ctx = Context::new(&surface)?;
+    ctx.select_font_face("sans-serif", FontSlant::Normal, FontWeight::Normal);
+    ctx.set_font_size(9.0);
+
+    let cell_width = (PAGE_WIDTH - 2.0 * MARGIN - (COLUMNS - 1) as f64 * GAP) / COLUMNS as f64;
+    for (i, entry) in entries.iter().enumerate() {
+        let meta = &entry.meta;
+        let size = meta.size();
+        let scale = cell_width / size.width() as f64;
+        let cell_height = size.height() as f64 * scale;
+        let rows =
+            (((PAGE_HEIGHT - 2.0 * MARGIN + GAP) / (cell_height + CAPTION + GAP)) as usize).max(1);
+        let per_page = rows * COLUMNS;
+        if i > 0 && i % per_page == 0 {
+            ctx.show_page()?;
+        }
+
+        let x = MARGIN + (i % COLUMNS) as f64 * (cell_width + GAP);
+        let y = MARGIN + ((i % per_page) / COLUMNS) as f64 * (cell_height + CAPTION + GAP);
+        ctx.save()?;
+        ctx.rectangle(x, y, cell_width, cell_height);
+        ctx.clip();
+        ctx.translate(x, y);
+        ctx.scale(scale, scale);
+        if entry.path.extension().and_then(|e| e.to_str()) == Some("png") {
+            let mut file = fs::File::open(&entry.path)?;
+            let image = ImageSurface::create_from_png(&mut file)?;
+            ctx.set_source_surface(&image, 0.0, 0.0)?;
+            ctx.paint()?;
+        } else {
+            redraw(meta)?.draw(&ctx)?;
+        }
+        ctx.restore()?;
+
+        let theme = theme_label(meta)
+            .map(|t| format!(", theme {}", t))
+            .unwrap_or_default();
+        ctx.set_source_rgb(0.0, 0.0, 0.0);
+        ctx.move_to(x, y + cell_height + CAPTION - 4.0);
+        ctx.show_text(&format!("{} {}{}", meta.sketch(), meta.seed(), theme))?;
+    }
+    surface.finish();
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use crate::{
+    common::{Format, Seed},
+    error::SketchError,
+    metadata::Metadata,
+    params::Params,
+    sketch::{self, Sketch},
+    RenderOpts, Size, ThemeChoice, Themes,
+};
+use cairo::{Context, ImageSurface, PdfSurface, SvgSurface};
+use clap::ArgMatches;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+/// The settings for a render that don't depend on the sketch.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub(super) seed: Seed,
+    pub(super) size: Size,
+    pub(super) themes: String,
+    pub(super) format: Format,
+    pub(super) dest: String,
+    pub(super) overrides: Vec<(String, String)>,
+    pub(super) theme: ThemeChoice,
+}
+
+/// A single render of a sketch to a file.
+pub struct Job<'a> {
+    settings: Settings,
+    sketch: &'a dyn Sketch,
+    matches: ArgMatches,
+    themes: Themes,
+    picked: Arc<Mutex<Option<usize>>>,
+    params: Params,
+}
+
+impl<'a> Job<'a> {
+    pub fn new(
+        settings: Settings,
+        sketch: &'a dyn Sketch,
+        matches: ArgMatches,
+        themes: Themes,
+    ) -> Self {
+        let params = Params::new(sketch.name(), settings.overrides.clone());
+        Self {
+            settings,
+            sketch,
+            matches,
+            themes,
+            picked: Arc::new(Mutex::new(None)),
+            params,
+        }
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn sketch(&self) -> &dyn Sketch {
+        self.sketch
+    }
+
+    /// The arguments that were given to the sketch's subcommand.
+    pub fn sketch_args(&self) -> Vec<String> {
+        sketch::args_of(&self.sketch.command(), &self.matches)
+    }
+
+    /// The index of the theme the sketch picked, once it has been rendered.
+    pub fn picked(&self) -> Option<usize> {
+        *self.picked.lock().unwrap()
+    }
+
+    pub fn metadata(&self) -> Metadata {
+        Metadata::new(
+            self.sketch.name(),
+            self.settings.seed,
+            self.settings.size,
+            &self.settings.themes,
+            self.picked(),
+            self.params.resolved(),
+            self.sketch_args(),
+        )
+        .with_colors(self.settings.theme.colors().to_vec())
+    }
+
+    pub fn dest(&self) -> Result<PathBuf, SketchError> {
+        let v = template::render(&self.settings.dest, &template::Context::from_job(self)).map_err(
+            |source| SketchError::Template {
+                template: self.settings.dest.clone(),
+                source,
+            },
+        )?;
+        Ok(PathBuf::from(v))
+    }
+
+    /// Draws the sketch into `ctx`, which is expected to be the job's size.
+    pub fn draw(&self, ctx: &Context) -> Result<(), SketchError> {
+        self.params.validate(self.sketch.params())?;
+        self.sketch.render(self, ctx, &self.matches)
+    }
+
+    /// Renders the sketch to the file named by the dest template.
+    pub fn render(&self) -> Result<PathBuf, SketchError> {
+        let size = self.size();
+        let dest = self.dest()?;
+        let surface_err = |source| SketchError::Surface {
+            path: dest.clone(),
+            source,
+        };
+
+        if let Some(dir) = dest.parent() {
+            fs::create_dir_all(dir).ok();
+        }
+
+        match self.settings.format {
+            Format::Pdf => {
+                let surface = PdfSurface::new(size.width() as f64, size.height() as f64, &dest)
+                    .map_err(surface_err)?;
+                let ctx = Context::new(&surface).map_err(surface_err)?;
+                self.draw(&ctx)?;
+                self.metadata().apply_to_pdf(&surface)?;
+                surface.finish();
+                Ok(dest)
+            }
+            Format::Png => {
+                let surface =
+                    ImageSurface::create(cairo::Format::ARgb32, size.width(), size.height())
+                        .map_err(surface_err)?;
+                let ctx = Context::new(&surface).map_err(surface_err)?;
+                self.draw(&ctx)?;
+                self.metadata().write_png(&surface, &dest)?;
+                Ok(dest)
+            }
+            Format::Svg => {
+                let surface =
+                    SvgSurface::new(size.width() as f64, size.height() as f64, Some(&dest))
+                        .map_err(surface_err)?;
+                let ctx = Context::new(&surface).map_err(surface_err)?;
+                self.draw(&ctx)?;
+                surface.finish();
+                self.metadata().apply_to_svg(&dest)?;
+                Ok(dest)
+            }
+        }
+    }
+}
+
+impl<'a> RenderOpts for Job<'a> {
+    fn size(&self) -> Size {
+        self.settings.size
+    }
+
+    fn rng(&self) -> Pcg64 {
+        Pcg64::seed_from_u64(self.settings.seed.value())
+    }
+
+    fn themes(&self) -> Result<Themes, SketchError> {
+        Ok(self
+            .themes
+            .clone()
+            .choose(&self.settings.theme)?
+            .record_picks(self.picked.clone()))
+    }
+
+    fn params(&self) -> &Params {
+        &self.params
+    }
+}
+
+mod template {
+    use super::Job;
+    use serde::Serialize;
+    use tinytemplate::{error::Error, TinyTemplate};
+
+    #[derive(Serialize)]
+    pub struct Context {
+        seed: String,
+        name: String,
+        extension: String,
+    }
+
+    impl Context {
+        pub fn from_job(job: &Job) -> Context {
+            Context {
+                seed: format!("{}", job.settings.seed),
+                name: job.sketch.name().to_owned(),
+                extension: job.settings.format.extension().to_owned(),
+            }
+        }
+    }
+
+    pub fn render(tpl: &str, ctx: &Context) -> Result<String, Error> {
+        let mut tt = TinyTemplate::new();
+        tt.add_template("t", tpl)?;
+        let res = tt.render("t", ctx)?;
+        Ok(res)
+    }
+}
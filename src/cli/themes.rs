@@ -0,0 +1,141 @@
+use crate::{Color, Themes};
+use clap::{Parser, Subcommand};
+use palette::Format;
+use std::{
+    collections::HashSet,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+mod palette;
+
+#[derive(Parser, Debug)]
+#[command(name = "themes", about = "Build and edit themes files")]
+struct Args {
+    /// The themes file to edit
+    #[arg(long, default_value_t=String::from("themes.bin"))]
+    themes: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write the themes in palette files to the themes file
+    Import {
+        #[arg(required = true)]
+        src: Vec<PathBuf>,
+
+        /// The format of the palette files, if not given by their extension
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+
+        /// Add to the existing themes rather than replacing them
+        #[arg(long, default_value_t = false)]
+        append: bool,
+    },
+
+    /// Write the themes in the themes file to a palette file
+    Export {
+        dst: PathBuf,
+
+        /// The format of the palette file, if not given by its extension
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+    },
+
+    /// Remove themes that have the same colors as an earlier theme
+    Dedupe,
+
+    /// Remove the themes at the given indices
+    Delete {
+        #[arg(required = true)]
+        indices: Vec<usize>,
+    },
+}
+
+fn format_of(path: &Path, format: Option<Format>) -> Result<Format, Box<dyn Error>> {
+    format.or_else(|| Format::from_path(path)).ok_or_else(|| {
+        format!(
+            "unknown palette format for {}, use --format",
+            path.display()
+        )
+        .into()
+    })
+}
+
+// The themes are read into memory rather than mapped, since the same file is
+// written back afterwards.
+fn load(src: &str) -> Result<Vec<Vec<Color>>, Box<dyn Error>> {
+    let themes = Themes::from_reader(fs::File::open(src)?).map_err(|e| e.at(src.into()))?;
+    Ok(themes.iter().collect())
+}
+
+fn save(dst: &str, themes: Vec<Vec<Color>>) -> Result<(), Box<dyn Error>> {
+    let themes = Themes::from_colors(themes)?;
+    fs::write(dst, themes.as_bytes())?;
+    println!("wrote {} themes to {}", themes.len(), dst);
+    Ok(())
+}
+
+/// Runs the `themes` command line tool, which converts between themes files and
+/// common palette formats.
+pub fn themes() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    match args.command {
+        Command::Import {
+            src,
+            format,
+            append,
+        } => {
+            let mut themes = if append {
+                load(&args.themes)?
+            } else {
+                Vec::new()
+            };
+            for src in src.iter() {
+                let format = format_of(src, format)?;
+                let imported = format
+                    .decode(&fs::read(src)?)
+                    .map_err(|e| format!("{}: {}", src.display(), e))?;
+                themes.extend(imported);
+            }
+            save(&args.themes, themes)
+        }
+        Command::Export { dst, format } => {
+            let format = format_of(&dst, format)?;
+            let themes = load(&args.themes)?;
+            fs::write(&dst, format.encode(&themes)?)?;
+            println!("wrote {} themes to {}", themes.len(), dst.display());
+            Ok(())
+        }
+        Command::Dedupe => {
+            let mut seen = HashSet::new();
+            let themes = load(&args.themes)?
+                .into_iter()
+                .filter(|theme| {
+                    seen.insert(theme.iter().map(Color::to_rgb_u32).collect::<Vec<_>>())
+                })
+                .collect::<Vec<_>>();
+            save(&args.themes, themes)
+        }
+        Command::Delete { indices } => {
+            let themes = load(&args.themes)?;
+            if let Some(ix) = indices.iter().find(|&&ix| ix >= themes.len()) {
+                return Err(format!("there is no theme {}, there are {}", ix, themes.len()).into());
+            }
+            if (0..themes.len()).all(|ix| indices.contains(&ix)) {
+                return Err("cannot delete every theme, the themes file needs at least one".into());
+            }
+            let themes = themes
+                .into_iter()
+                .enumerate()
+                .filter(|(ix, _)| !indices.contains(ix))
+                .map(|(_, theme)| theme)
+                .collect();
+            save(&args.themes, themes)
+        }
+    }
+}
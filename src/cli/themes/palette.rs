@@ -0,0 +1,484 @@
+use crate::{Color, THEME_COLORS};
+use clap::ValueEnum;
+use std::{error::Error, fmt, path::Path};
+
+/// The text and swatch formats that themes can be imported from and exported
+/// to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// An array of themes, each an array of hex codes
+    Json,
+    /// One theme of hex codes per line
+    Csv,
+    /// A GIMP palette
+    Gpl,
+    /// An Adobe swatch exchange file
+    Ase,
+}
+
+impl Format {
+    /// Guesses the format from the extension of `path`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            "gpl" => Some(Format::Gpl),
+            "ase" => Some(Format::Ase),
+            _ => None,
+        }
+    }
+
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<Vec<Color>>, Box<dyn Error>> {
+        match self {
+            Format::Json => decode_json(data),
+            Format::Csv => decode_csv(std::str::from_utf8(data)?),
+            Format::Gpl => decode_gpl(std::str::from_utf8(data)?),
+            Format::Ase => decode_ase(data),
+        }
+    }
+
+    pub fn encode(&self, themes: &[Vec<Color>]) -> Result<Vec<u8>, Box<dyn Error>> {
+        match self {
+            Format::Json => encode_json(themes),
+            Format::Csv => Ok(encode_csv(themes).into_bytes()),
+            Format::Gpl => Ok(encode_gpl(themes).into_bytes()),
+            Format::Ase => Ok(encode_ase(themes)),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Format::Json => "json",
+            Format::Csv => "csv",
+            Format::Gpl => "gpl",
+            Format::Ase => "ase",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Colors in text formats can be anything that `Color` parses, though they are
+// written as hex codes.
+fn parse_color(s: &str) -> Result<Color, Box<dyn Error>> {
+    Ok(s.parse::<Color>()?)
+}
+
+// Groups a flat list of colors into themes, for the palette formats that don't
+// have a notion of a theme.
+#[allow(clippy::manual_is_multiple_of)]
+fn into_themes(colors: Vec<Color>) -> Result<Vec<Vec<Color>>, Box<dyn Error>> {
+    if colors.len() % THEME_COLORS != 0 {
+        return Err(format!(
+            "found {} colors, which is not a whole number of {}-color themes",
+            colors.len(),
+            THEME_COLORS
+        )
+        .into());
+    }
+    Ok(colors.chunks(THEME_COLORS).map(|c| c.to_vec()).collect())
+}
+
+fn decode_json(data: &[u8]) -> Result<Vec<Vec<Color>>, Box<dyn Error>> {
+    let themes: Vec<Vec<String>> = serde_json::from_slice(data)?;
+    themes
+        .iter()
+        .map(|theme| theme.iter().map(|c| parse_color(c)).collect())
+        .collect()
+}
+
+fn encode_json(themes: &[Vec<Color>]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let themes = themes
+        .iter()
+        .map(|theme| theme.iter().map(|c| c.to_string()).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let mut data = serde_json::to_vec_pretty(&themes)?;
+    data.push(b'\n');
+    Ok(data)
+}
+
+fn decode_csv(text: &str) -> Result<Vec<Vec<Color>>, Box<dyn Error>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| {
+            line.split(',')
+                .map(parse_color)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("line {}: {}", n + 1, e).into())
+        })
+        .collect()
+}
+
+fn encode_csv(themes: &[Vec<Color>]) -> String {
+    themes
+        .iter()
+        .map(|theme| {
+            let row = theme.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+            format!("{}\n", row.join(","))
+        })
+        .collect()
+}
+
+fn decode_gpl(text: &str) -> Result<Vec<Vec<Color>>, Box<dyn Error>> {
+    let mut lines = text.lines().enumerate();
+    match lines.next() {
+        Some((_, header)) if header.trim() == "GIMP Palette" => {}
+        _ => return Err("not a GIMP palette".into()),
+    }
+    let mut colors = Vec::new();
+    for (n, line) in lines {
+        let line = line.trim();
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with("Name:")
+            || line.starts_with("Columns:")
+        {
+            continue;
+        }
+        let rgb = line
+            .split_whitespace()
+            .take(3)
+            .map(|v| v.parse::<u8>())
+            .collect::<Result<Vec<_>, _>>()
+            .ok()
+            .filter(|rgb| rgb.len() == 3)
+            .ok_or_else(|| format!("line {}: expected a color, got {}", n + 1, line))?;
+        colors.push(Color::from_rgb(rgb[0], rgb[1], rgb[2]));
+    }
+    into_themes(colors)
+}
+
+fn encode_gpl(themes: &[Vec<Color>]) -> String {
+    let mut text = format!("GIMP Palette\nName: themes\nColumns: {}\n#\n", THEME_COLORS);
+    for (i, theme) in themes.iter().enumerate() {
+        for (j, c) in theme.iter().enumerate() {
+            text.push_str(&format!(
+                "{:3} {:3} {:3}\ttheme {} color {}\n",
+                c.r(),
+                c.g(),
+                c.b(),
+                i,
+                j
+            ));
+        }
+    }
+    text
+}
+
+const ASE_SIGNATURE: &[u8] = b"ASEF";
+const ASE_GROUP_START: u16 = 0xc001;
+const ASE_GROUP_END: u16 = 0xc002;
+const ASE_COLOR: u16 = 0x0001;
+
+// A cursor over the big-endian fields of an ASE file.
+struct Reader<'a> {
+    data: &'a [u8],
+    off: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        let v = self
+            .data
+            .get(self.off..self.off + n)
+            .ok_or("ase file is truncated")?;
+        self.off += n;
+        Ok(v)
+    }
+
+    fn u16(&mut self) -> Result<u16, Box<dyn Error>> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, Box<dyn Error>> {
+        Ok(f32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    // A length prefixed, nul terminated UTF-16 string.
+    fn name(&mut self) -> Result<String, Box<dyn Error>> {
+        let len = self.u16()? as usize;
+        let units = self
+            .take(len * 2)?
+            .chunks(2)
+            .map(|u| u16::from_be_bytes([u[0], u[1]]))
+            .take_while(|&u| u != 0)
+            .collect::<Vec<_>>();
+        Ok(String::from_utf16_lossy(&units))
+    }
+}
+
+fn unit(v: f32) -> u8 {
+    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+// Each group is read as a theme, and must hold exactly one theme's worth of
+// colors. Colors outside of any group are split into themes in order, as for
+// the formats without groups.
+fn decode_ase(data: &[u8]) -> Result<Vec<Vec<Color>>, Box<dyn Error>> {
+    let mut r = Reader { data, off: 0 };
+    if r.take(4)? != ASE_SIGNATURE {
+        return Err("not an ase file".into());
+    }
+    r.take(4)?; // version
+    let count = r.u32()?;
+    let mut themes = Vec::new();
+    let mut loose = Vec::new();
+    let mut group: Option<(String, Vec<Color>)> = None;
+    for _ in 0..count {
+        let kind = r.u16()?;
+        let len = r.u32()? as usize;
+        let mut b = Reader {
+            data: r.take(len)?,
+            off: 0,
+        };
+        match kind {
+            ASE_GROUP_START => {
+                if let Some((name, _)) = &group {
+                    return Err(format!("ase group {:?} is not closed", name).into());
+                }
+                themes.extend(into_themes(std::mem::take(&mut loose))?);
+                group = Some((b.name()?, Vec::new()));
+            }
+            ASE_GROUP_END => {
+                let (name, colors) = group.take().ok_or("ase group end without a start")?;
+                if colors.len() != THEME_COLORS {
+                    return Err(format!(
+                        "ase group {:?} has {} colors, but a theme has {}",
+                        name,
+                        colors.len(),
+                        THEME_COLORS
+                    )
+                    .into());
+                }
+                themes.push(colors);
+            }
+            ASE_COLOR => {
+                let color = ase_color(&mut b)?;
+                match &mut group {
+                    Some((_, colors)) => colors.push(color),
+                    None => loose.push(color),
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some((name, _)) = group {
+        return Err(format!("ase group {:?} is not closed", name).into());
+    }
+    themes.extend(into_themes(loose)?);
+    Ok(themes)
+}
+
+fn ase_color(b: &mut Reader) -> Result<Color, Box<dyn Error>> {
+    b.name()?;
+    Ok(match b.take(4)? {
+        b"RGB " => Color::from_rgb(unit(b.f32()?), unit(b.f32()?), unit(b.f32()?)),
+        b"Gray" => {
+            let v = unit(b.f32()?);
+            Color::from_rgb(v, v, v)
+        }
+        b"CMYK" => {
+            let (c, m, y, k) = (b.f32()?, b.f32()?, b.f32()?, b.f32()?);
+            Color::from_rgb(
+                unit((1.0 - c) * (1.0 - k)),
+                unit((1.0 - m) * (1.0 - k)),
+                unit((1.0 - y) * (1.0 - k)),
+            )
+        }
+        model => {
+            return Err(format!(
+                "unsupported ase color model: {}",
+                String::from_utf8_lossy(model).trim()
+            )
+            .into())
+        }
+    })
+}
+
+fn ase_name(name: &str) -> Vec<u8> {
+    let units = name.encode_utf16().chain(Some(0)).collect::<Vec<_>>();
+    let mut data = (units.len() as u16).to_be_bytes().to_vec();
+    for u in units {
+        data.extend_from_slice(&u.to_be_bytes());
+    }
+    data
+}
+
+fn ase_block(out: &mut Vec<u8>, kind: u16, data: &[u8]) {
+    out.extend_from_slice(&kind.to_be_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(data);
+}
+
+// Each theme is written as a group so that it stays together in other tools.
+fn encode_ase(themes: &[Vec<Color>]) -> Vec<u8> {
+    let mut blocks = Vec::new();
+    let mut count = 0u32;
+    for (i, theme) in themes.iter().enumerate() {
+        ase_block(
+            &mut blocks,
+            ASE_GROUP_START,
+            &ase_name(&format!("theme {}", i)),
+        );
+        for c in theme {
+            let mut data = ase_name(&c.to_string());
+            data.extend_from_slice(b"RGB ");
+            for v in [c.r_f64(), c.g_f64(), c.b_f64()] {
+                data.extend_from_slice(&(v as f32).to_be_bytes());
+            }
+            // global color
+            data.extend_from_slice(&0u16.to_be_bytes());
+            ase_block(&mut blocks, ASE_COLOR, &data);
+        }
+        ase_block(&mut blocks, ASE_GROUP_END, &[]);
+        count += theme.len() as u32 + 2;
+    }
+
+    let mut out = ASE_SIGNATURE.to_vec();
+    out.extend_from_slice(&1u16.to_be_bytes());
+    out.extend_from_slice(&0u16.to_be_bytes());
+    out.extend_from_slice(&count.to_be_bytes());
+    out.extend_from_slice(&blocks);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn themes() -> Vec<Vec<Color>> {
+        [
+            [0x1d2b53, 0xff004d, 0xffa300, 0x29adff, 0x00e436],
+            [0x000000, 0xffffff, 0x808080, 0x010203, 0xfefdfc],
+        ]
+        .iter()
+        .map(|theme| theme.iter().map(|&c| Color::from_rgb_u32(c)).collect())
+        .collect()
+    }
+
+    fn rgb(themes: &[Vec<Color>]) -> Vec<Vec<u32>> {
+        themes
+            .iter()
+            .map(|theme| theme.iter().map(Color::to_rgb_u32).collect())
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        for format in [Format::Json, Format::Csv, Format::Gpl, Format::Ase] {
+            let data = format.encode(&themes()).unwrap();
+            let decoded = format.decode(&data).unwrap();
+            assert_eq!(rgb(&decoded), rgb(&themes()), "{}", format);
+        }
+    }
+
+    #[test]
+    fn round_trip_empty() {
+        for format in [Format::Json, Format::Csv, Format::Gpl, Format::Ase] {
+            let data = format.encode(&[]).unwrap();
+            assert!(format.decode(&data).unwrap().is_empty(), "{}", format);
+        }
+    }
+
+    #[test]
+    fn partial_theme() {
+        let text = "GIMP Palette\n0 0 0\n255 255 255\n";
+        assert!(Format::Gpl.decode(text.as_bytes()).is_err());
+    }
+
+    // An ase file with a group for each of `groups` and then `loose` colors
+    // outside of any group.
+    fn ase(groups: &[&[u32]], loose: &[u32]) -> Vec<u8> {
+        let color = |blocks: &mut Vec<u8>, c: u32| {
+            let mut data = ase_name("");
+            data.extend_from_slice(b"Gray");
+            data.extend_from_slice(&(c as f32 / 255.0).to_be_bytes());
+            data.extend_from_slice(&2u16.to_be_bytes());
+            ase_block(blocks, ASE_COLOR, &data);
+        };
+        let mut blocks = Vec::new();
+        let mut count = 0;
+        for (i, group) in groups.iter().enumerate() {
+            ase_block(&mut blocks, ASE_GROUP_START, &ase_name(&format!("g{}", i)));
+            for &c in group.iter() {
+                color(&mut blocks, c);
+            }
+            ase_block(&mut blocks, ASE_GROUP_END, &[]);
+            count += group.len() + 2;
+        }
+        for &c in loose {
+            color(&mut blocks, c);
+        }
+        count += loose.len();
+
+        let mut out = ASE_SIGNATURE.to_vec();
+        out.extend_from_slice(&[0, 1, 0, 0]);
+        out.extend_from_slice(&(count as u32).to_be_bytes());
+        out.extend_from_slice(&blocks);
+        out
+    }
+
+    fn grays(themes: &[Vec<Color>]) -> Vec<Vec<u8>> {
+        themes
+            .iter()
+            .map(|theme| theme.iter().map(|c| c.r()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn ase_groups() {
+        let decoded = Format::Ase
+            .decode(&ase(&[&[1, 2, 3, 4, 5], &[6, 7, 8, 9, 10]], &[]))
+            .unwrap();
+        assert_eq!(
+            grays(&decoded),
+            vec![vec![1, 2, 3, 4, 5], vec![6, 7, 8, 9, 10]]
+        );
+
+        // loose colors are still read in fives.
+        let decoded = Format::Ase
+            .decode(&ase(&[&[1, 2, 3, 4, 5]], &[6, 7, 8, 9, 10]))
+            .unwrap();
+        assert_eq!(
+            grays(&decoded),
+            vec![vec![1, 2, 3, 4, 5], vec![6, 7, 8, 9, 10]]
+        );
+
+        // a group is a theme, even when the colors would add up to whole themes.
+        let err = Format::Ase
+            .decode(&ase(&[&[1, 2, 3], &[4, 5, 6, 7, 8, 9, 10]], &[]))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ase group \"g0\" has 3 colors, but a theme has 5"
+        );
+        assert!(Format::Ase.decode(&ase(&[&[]], &[])).is_err());
+    }
+
+    #[test]
+    fn ase_unbalanced_groups() {
+        let mut data = ase(&[&[1, 2, 3, 4, 5]], &[]);
+        // drop the group end block, which is the last 6 bytes.
+        data.truncate(data.len() - 6);
+        data[11] -= 1;
+        assert!(Format::Ase.decode(&data).is_err());
+
+        let mut data = ase(&[], &[]);
+        ase_block(&mut data, ASE_GROUP_END, &[]);
+        data[11] = 1;
+        assert!(Format::Ase.decode(&data).is_err());
+    }
+
+    #[test]
+    fn truncated_ase() {
+        let data = Format::Ase.encode(&themes()).unwrap();
+        assert!(Format::Ase.decode(&data[..data.len() - 3]).is_err());
+    }
+}
@@ -0,0 +1,903 @@
+use crate::Color;
+use std::str::FromStr;
+
+/// Hue in degrees, with saturation and lightness from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub h: f64,
+    pub s: f64,
+    pub l: f64,
+}
+
+/// Hue in degrees, with saturation and value from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub h: f64,
+    pub s: f64,
+    pub v: f64,
+}
+
+/// sRGB without the transfer function, so that the channels are proportional
+/// to light intensity. Channels are from 0 to 1 for colors in the sRGB gamut.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearRgb {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+}
+
+/// CIE L*a*b* relative to the D65 white point, with `l` from 0 to 100.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+/// CIE LCh, the polar form of `Lab`, with the hue in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lch {
+    pub l: f64,
+    pub c: f64,
+    pub h: f64,
+}
+
+/// Björn Ottosson's OKLab, with `l` from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+/// OKLCh, the polar form of `Oklab`, with the hue in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklch {
+    pub l: f64,
+    pub c: f64,
+    pub h: f64,
+}
+
+// D65, normalized so that Y is 1.
+const WHITE: (f64, f64, f64) = (0.95047, 1.0, 1.08883);
+
+fn to_u8(v: f64) -> u8 {
+    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn to_linear(v: f64) -> f64 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(v: f64) -> f64 {
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn to_polar(a: f64, b: f64) -> (f64, f64) {
+    (a.hypot(b), b.atan2(a).to_degrees().rem_euclid(360.0))
+}
+
+fn from_polar(c: f64, h: f64) -> (f64, f64) {
+    let h = h.to_radians();
+    (c * h.cos(), c * h.sin())
+}
+
+// The hue of an RGB color in degrees, given its largest channel and chroma.
+fn hue(r: f64, g: f64, b: f64, max: f64, chroma: f64) -> f64 {
+    if chroma == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
+    }
+}
+
+// The RGB channels for a hue and chroma, before the lightness is added in.
+fn from_hue(h: f64, chroma: f64) -> (f64, f64, f64) {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    }
+}
+
+impl LinearRgb {
+    fn in_gamut(&self) -> bool {
+        const EPS: f64 = 1e-6;
+        [self.r, self.g, self.b]
+            .iter()
+            .all(|v| (-EPS..=1.0 + EPS).contains(v))
+    }
+}
+
+impl Oklch {
+    // Reduces the chroma until the color fits in sRGB, which keeps the
+    // lightness and hue rather than clipping the channels.
+    fn to_gamut(self) -> LinearRgb {
+        let rgb = Color::oklab_to_linear(self.to_oklab());
+        if rgb.in_gamut() {
+            return rgb;
+        }
+        let (mut lo, mut hi) = (0.0, self.c);
+        for _ in 0..24 {
+            let c = (lo + hi) / 2.0;
+            if Color::oklab_to_linear(Oklch { c, ..self }.to_oklab()).in_gamut() {
+                lo = c;
+            } else {
+                hi = c;
+            }
+        }
+        Color::oklab_to_linear(Oklch { c: lo, ..self }.to_oklab())
+    }
+
+    fn to_oklab(self) -> Oklab {
+        let (a, b) = from_polar(self.c, self.h);
+        Oklab { l: self.l, a, b }
+    }
+}
+
+impl Color {
+    pub fn to_hsl(&self) -> Hsl {
+        let (r, g, b) = self.as_f64();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let l = (max + min) / 2.0;
+        let s = if chroma == 0.0 {
+            0.0
+        } else {
+            chroma / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        Hsl {
+            h: hue(r, g, b, max, chroma),
+            s,
+            l,
+        }
+    }
+
+    pub fn from_hsl(hsl: Hsl) -> Self {
+        let chroma = (1.0 - (2.0 * hsl.l - 1.0).abs()) * hsl.s;
+        let (r, g, b) = from_hue(hsl.h, chroma);
+        let m = hsl.l - chroma / 2.0;
+        Self::from_rgb(to_u8(r + m), to_u8(g + m), to_u8(b + m))
+    }
+
+    pub fn to_hsv(&self) -> Hsv {
+        let (r, g, b) = self.as_f64();
+        let max = r.max(g).max(b);
+        let chroma = max - r.min(g).min(b);
+        Hsv {
+            h: hue(r, g, b, max, chroma),
+            s: if max == 0.0 { 0.0 } else { chroma / max },
+            v: max,
+        }
+    }
+
+    pub fn from_hsv(hsv: Hsv) -> Self {
+        let chroma = hsv.v * hsv.s;
+        let (r, g, b) = from_hue(hsv.h, chroma);
+        let m = hsv.v - chroma;
+        Self::from_rgb(to_u8(r + m), to_u8(g + m), to_u8(b + m))
+    }
+
+    pub fn to_linear(&self) -> LinearRgb {
+        let (r, g, b) = self.as_f64();
+        LinearRgb {
+            r: to_linear(r),
+            g: to_linear(g),
+            b: to_linear(b),
+        }
+    }
+
+    /// The color nearest to `rgb`, with channels outside of sRGB clipped.
+    pub fn from_linear(rgb: LinearRgb) -> Self {
+        Self::from_rgb(
+            to_u8(from_linear(rgb.r)),
+            to_u8(from_linear(rgb.g)),
+            to_u8(from_linear(rgb.b)),
+        )
+    }
+
+    pub fn to_lab(&self) -> Lab {
+        let LinearRgb { r, g, b } = self.to_linear();
+        let x = 0.4124564 * r + 0.3575761 * g + 0.1804375 * b;
+        let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+        let z = 0.0193339 * r + 0.1191920 * g + 0.9503041 * b;
+        let f = |t: f64| {
+            const D: f64 = 6.0 / 29.0;
+            if t > D * D * D {
+                t.cbrt()
+            } else {
+                t / (3.0 * D * D) + 4.0 / 29.0
+            }
+        };
+        let (fx, fy, fz) = (f(x / WHITE.0), f(y / WHITE.1), f(z / WHITE.2));
+        Lab {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+
+    pub fn from_lab(lab: Lab) -> Self {
+        let f = |t: f64| {
+            const D: f64 = 6.0 / 29.0;
+            if t > D {
+                t * t * t
+            } else {
+                3.0 * D * D * (t - 4.0 / 29.0)
+            }
+        };
+        let fy = (lab.l + 16.0) / 116.0;
+        let x = WHITE.0 * f(fy + lab.a / 500.0);
+        let y = WHITE.1 * f(fy);
+        let z = WHITE.2 * f(fy - lab.b / 200.0);
+        Self::from_linear(LinearRgb {
+            r: 3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+            g: -0.9692660 * x + 1.8760108 * y + 0.0415560 * z,
+            b: 0.0556434 * x - 0.2040259 * y + 1.0572252 * z,
+        })
+    }
+
+    pub fn to_lch(&self) -> Lch {
+        let Lab { l, a, b } = self.to_lab();
+        let (c, h) = to_polar(a, b);
+        Lch { l, c, h }
+    }
+
+    pub fn from_lch(lch: Lch) -> Self {
+        let (a, b) = from_polar(lch.c, lch.h);
+        Self::from_lab(Lab { l: lch.l, a, b })
+    }
+
+    pub fn to_oklab(&self) -> Oklab {
+        let LinearRgb { r, g, b } = self.to_linear();
+        let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+        let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+        let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+        Oklab {
+            l: 0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+            a: 1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+            b: 0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+        }
+    }
+
+    fn oklab_to_linear(lab: Oklab) -> LinearRgb {
+        let l = (lab.l + 0.3963377774 * lab.a + 0.2158037573 * lab.b).powi(3);
+        let m = (lab.l - 0.1055613458 * lab.a - 0.0638541728 * lab.b).powi(3);
+        let s = (lab.l - 0.0894841775 * lab.a - 1.2914855480 * lab.b).powi(3);
+        LinearRgb {
+            r: 4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+            g: -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+            b: -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+        }
+    }
+
+    pub fn from_oklab(lab: Oklab) -> Self {
+        Self::from_linear(Self::oklab_to_linear(lab))
+    }
+
+    pub fn to_oklch(&self) -> Oklch {
+        let Oklab { l, a, b } = self.to_oklab();
+        let (c, h) = to_polar(a, b);
+        Oklch { l, c, h }
+    }
+
+    /// The color for `lch`. Colors outside of sRGB lose chroma until they fit,
+    /// rather than having their channels clipped, so the hue is kept.
+    pub fn from_oklch(lch: Oklch) -> Self {
+        Self::from_linear(lch.to_gamut())
+    }
+
+    /// The relative luminance as defined by WCAG, from 0 for black to 1 for
+    /// white.
+    pub fn relative_luminance(&self) -> f64 {
+        let LinearRgb { r, g, b } = self.to_linear();
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+
+    /// The WCAG contrast ratio between two colors, from 1 to 21.
+    pub fn contrast(&self, other: &Color) -> f64 {
+        let (a, b) = (self.relative_luminance(), other.relative_luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    // Applies `f` in OKLCh and keeps the alpha.
+    fn adjust<F: FnOnce(Oklch) -> Oklch>(&self, f: F) -> Self {
+        let c = Self::from_oklch(f(self.to_oklch()));
+        Self { a: self.a, ..c }
+    }
+
+    /// Raises the OKLab lightness by `amount`, where the whole range from black
+    /// to white is 1, keeping the hue.
+    pub fn lighten(&self, amount: f64) -> Self {
+        self.adjust(|c| Oklch {
+            l: (c.l + amount).clamp(0.0, 1.0),
+            ..c
+        })
+    }
+
+    /// Lowers the OKLab lightness by `amount`, as with `lighten`.
+    pub fn darken(&self, amount: f64) -> Self {
+        self.lighten(-amount)
+    }
+
+    /// Scales the OKLCh chroma by `1 + amount`, so a negative amount
+    /// desaturates and -1 gives a gray of the same lightness.
+    pub fn saturate(&self, amount: f64) -> Self {
+        self.adjust(|c| Oklch {
+            c: (c.c * (1.0 + amount)).max(0.0),
+            ..c
+        })
+    }
+
+    /// Interpolates between two colors in OKLab, where `t` of 0 gives `self` and
+    /// 1 gives `other`.
+    pub fn mix(&self, other: &Color, t: f64) -> Self {
+        let (p, q) = (self.to_oklab(), other.to_oklab());
+        let lerp = |a: f64, b: f64| a + (b - a) * t;
+        let c = Self::from_oklab(Oklab {
+            l: lerp(p.l, q.l),
+            a: lerp(p.a, q.a),
+            b: lerp(p.b, q.b),
+        });
+        Self {
+            a: lerp(self.a as f64, other.a as f64)
+                .round()
+                .clamp(0.0, 255.0) as u8,
+            ..c
+        }
+    }
+}
+
+// The CSS named colors, sorted by name.
+const NAMED: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    // from_str_radix alone would also take a sign, as in +f.
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digit = |i: usize, n: usize| u8::from_str_radix(hex.get(i..i + n)?, 16).ok();
+    // a single digit stands for the digit repeated, so f is ff.
+    let short = |i: usize| digit(i, 1).map(|v| v * 17);
+    let long = |i: usize| digit(i * 2, 2);
+    let (r, g, b, a) = match hex.len() {
+        3 => (short(0)?, short(1)?, short(2)?, 0xff),
+        4 => (short(0)?, short(1)?, short(2)?, short(3)?),
+        6 => (long(0)?, long(1)?, long(2)?, 0xff),
+        8 => (long(0)?, long(1)?, long(2)?, long(3)?),
+        _ => return None,
+    };
+    Some(Color { r, g, b, a })
+}
+
+// Splits the arguments of a CSS color function, which are separated either by
+// commas or by spaces with a slash before the alpha.
+fn parse_args(args: &str) -> Vec<&str> {
+    args.split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+// A number, or a percentage of `scale`.
+fn parse_number(v: &str, scale: f64) -> Option<f64> {
+    match v.strip_suffix('%') {
+        Some(v) => v.parse::<f64>().ok().map(|v| v / 100.0 * scale),
+        None => v.parse().ok(),
+    }
+}
+
+fn parse_hue(v: &str) -> Option<f64> {
+    v.strip_suffix("deg").unwrap_or(v).parse().ok()
+}
+
+fn parse_alpha(v: Option<&&str>) -> Option<u8> {
+    match v {
+        Some(v) => Some(to_u8(parse_number(v, 1.0)?)),
+        None => Some(0xff),
+    }
+}
+
+fn parse_function(name: &str, args: &[&str]) -> Option<Color> {
+    if !(3..=4).contains(&args.len()) {
+        return None;
+    }
+    let c = match name {
+        "rgb" | "rgba" => {
+            let channel = |v: &str| parse_number(v, 255.0).map(|v| to_u8(v / 255.0));
+            Color::from_rgb(channel(args[0])?, channel(args[1])?, channel(args[2])?)
+        }
+        "hsl" | "hsla" => Color::from_hsl(Hsl {
+            h: parse_hue(args[0])?,
+            s: parse_number(args[1], 1.0)?,
+            l: parse_number(args[2], 1.0)?,
+        }),
+        // CSS gives a percentage chroma relative to 0.4.
+        "oklch" => Color::from_oklch(Oklch {
+            l: parse_number(args[0], 1.0)?,
+            c: parse_number(args[1], 0.4)?,
+            h: parse_hue(args[2])?,
+        }),
+        _ => return None,
+    };
+    Some(Color {
+        a: parse_alpha(args.get(3))?,
+        ..c
+    })
+}
+
+/// Parses the CSS color syntaxes `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`,
+/// `rgb()`, `rgba()`, `hsl()`, `hsla()`, `oklch()` and the named colors.
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let v = s.trim().to_ascii_lowercase();
+        let c = if let Some(hex) = v.strip_prefix('#') {
+            parse_hex(hex)
+        } else if let Some((name, args)) = v.strip_suffix(')').and_then(|v| v.split_once('(')) {
+            parse_function(name.trim(), &parse_args(args))
+        } else if v == "transparent" {
+            Some(Color::from_rgba(0, 0, 0, 0.0))
+        } else {
+            NAMED
+                .binary_search_by(|(name, _)| name.cmp(&v.as_str()))
+                .ok()
+                .map(|ix| Color::from_rgb_u32(NAMED[ix].1))
+        };
+        c.ok_or_else(|| format!("invalid color: {}", s))
+    }
+}
+
+impl Color {
+    /// Parses a comma separated list of colors in any of the syntaxes that
+    /// `from_str` accepts. Commas inside the parentheses of `rgb()` and the
+    /// like don't separate colors.
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, String> {
+        let mut colors = Vec::new();
+        let mut depth = 0usize;
+        let mut start = 0;
+        for (ix, c) in s.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    colors.push(s[start..ix].parse()?);
+                    start = ix + 1;
+                }
+                _ => {}
+            }
+        }
+        colors.push(s[start..].parse()?);
+        Ok(colors)
+    }
+
+    /// Writes colors as hex codes in the comma separated form that
+    /// `parse_list` reads.
+    pub fn format_list(colors: &[Color]) -> String {
+        colors
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hex_lengths() {
+        let c = |s: &str| s.parse::<Color>().map(|c| c.to_rgb_u32());
+        assert_eq!(c("#f80"), Ok(0xff8800));
+        assert_eq!(c("#ff8800"), Ok(0xff8800));
+        assert_eq!(c("#FF8800cc"), Ok(0xff8800));
+        assert!(c("#ff880").is_err());
+    }
+
+    #[test]
+    fn parse_hex_rejects_signs() {
+        for s in ["#+f0", "#+ff000", "#ff+f00", "#-f0", "#ff-0000f"] {
+            assert!(s.parse::<Color>().is_err(), "{}", s);
+        }
+    }
+
+    fn close(a: f64, b: f64, eps: f64) -> bool {
+        (a - b).abs() <= eps
+    }
+
+    // A spread of colors over the whole cube, including the corners.
+    fn samples() -> impl Iterator<Item = Color> {
+        let steps = [0u8, 1, 17, 64, 119, 128, 200, 254, 255];
+        steps.into_iter().flat_map(move |r| {
+            steps
+                .into_iter()
+                .flat_map(move |g| steps.into_iter().map(move |b| Color::from_rgb(r, g, b)))
+        })
+    }
+
+    fn rgb(c: Color) -> u32 {
+        c.to_rgb_u32()
+    }
+
+    #[test]
+    fn hsl_hsv() {
+        let orange = Color::from_rgb_u32(0xff8800);
+        let hsl = orange.to_hsl();
+        assert!(close(hsl.h, 32.0, 1e-9) && close(hsl.s, 1.0, 1e-9) && close(hsl.l, 0.5, 1e-9));
+        let hsv = orange.to_hsv();
+        assert!(close(hsv.h, 32.0, 1e-9) && close(hsv.s, 1.0, 1e-9) && close(hsv.v, 1.0, 1e-9));
+
+        let gray = Color::from_rgb_u32(0x808080).to_hsl();
+        assert_eq!((gray.h, gray.s), (0.0, 0.0));
+        let blue = Color::from_rgb_u32(0x0000ff).to_hsv();
+        assert!(close(blue.h, 240.0, 1e-9));
+        assert_eq!(
+            rgb(Color::from_hsl(Hsl {
+                h: -240.0,
+                s: 1.0,
+                l: 0.25
+            })),
+            0x008000
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        for c in samples() {
+            assert_eq!(rgb(Color::from_hsl(c.to_hsl())), rgb(c), "hsl {}", c);
+            assert_eq!(rgb(Color::from_hsv(c.to_hsv())), rgb(c), "hsv {}", c);
+            assert_eq!(
+                rgb(Color::from_linear(c.to_linear())),
+                rgb(c),
+                "linear {}",
+                c
+            );
+            assert_eq!(rgb(Color::from_lab(c.to_lab())), rgb(c), "lab {}", c);
+            assert_eq!(rgb(Color::from_lch(c.to_lch())), rgb(c), "lch {}", c);
+            assert_eq!(rgb(Color::from_oklab(c.to_oklab())), rgb(c), "oklab {}", c);
+            assert_eq!(rgb(Color::from_oklch(c.to_oklch())), rgb(c), "oklch {}", c);
+        }
+    }
+
+    #[test]
+    fn lab_reference() {
+        let white = Color::white().to_lab();
+        assert!(close(white.l, 100.0, 1e-3) && close(white.a, 0.0, 1e-3));
+        assert!(close(white.b, 0.0, 1e-3));
+        assert!(Color::black().to_lab().l.abs() < 1e-9);
+
+        // red in CIELAB under D65.
+        let red = Color::from_rgb_u32(0xff0000).to_lab();
+        assert!(close(red.l, 53.24, 0.01), "{:?}", red);
+        assert!(close(red.a, 80.09, 0.01), "{:?}", red);
+        assert!(close(red.b, 67.20, 0.01), "{:?}", red);
+        let red = Color::from_rgb_u32(0xff0000).to_lch();
+        assert!(
+            close(red.c, 104.55, 0.01) && close(red.h, 40.0, 0.05),
+            "{:?}",
+            red
+        );
+    }
+
+    #[test]
+    fn oklab_reference() {
+        // the values for the sRGB primaries given in the OKLab post.
+        for (c, l, a, b) in [
+            (0xff0000, 0.627955, 0.224863, 0.125846),
+            (0x00ff00, 0.866440, -0.233888, 0.179498),
+            (0x0000ff, 0.452014, -0.032457, -0.311528),
+        ] {
+            let lab = Color::from_rgb_u32(c).to_oklab();
+            assert!(close(lab.l, l, 1e-4), "{:06x} {:?}", c, lab);
+            assert!(close(lab.a, a, 1e-4), "{:06x} {:?}", c, lab);
+            assert!(close(lab.b, b, 1e-4), "{:06x} {:?}", c, lab);
+        }
+        let white = Color::white().to_oklch();
+        assert!(close(white.l, 1.0, 1e-4) && white.c < 1e-4);
+        let red = Color::from_rgb_u32(0xff0000).to_oklch();
+        assert!(
+            close(red.c, 0.2577, 1e-4) && close(red.h, 29.23, 0.01),
+            "{:?}",
+            red
+        );
+    }
+
+    #[test]
+    fn oklch_gamut_mapping() {
+        // far more chroma than sRGB holds at this lightness and hue.
+        let lch = Oklch {
+            l: 0.7,
+            c: 0.4,
+            h: 150.0,
+        };
+        let mapped = Color::from_oklch(lch).to_oklch();
+        assert!(close(mapped.l, lch.l, 0.01), "{:?}", mapped);
+        assert!(close(mapped.h, lch.h, 1.0), "{:?}", mapped);
+        assert!(mapped.c > 0.1 && mapped.c < lch.c, "{:?}", mapped);
+
+        // clipping the channels instead moves the hue a long way.
+        let clipped = Color::from_oklab(lch.to_oklab()).to_oklch();
+        assert!((clipped.h - lch.h).abs() > (mapped.h - lch.h).abs());
+
+        // lightness outside 0..1 maps to white and black.
+        let bright = Color::from_oklch(Oklch { l: 1.2, ..lch });
+        assert_eq!(rgb(bright), 0xffffff);
+    }
+
+    #[test]
+    fn wcag() {
+        assert_eq!(Color::black().relative_luminance(), 0.0);
+        assert!(close(Color::white().relative_luminance(), 1.0, 1e-12));
+        assert!(close(Color::black().contrast(&Color::white()), 21.0, 1e-9));
+        assert!(close(Color::white().contrast(&Color::black()), 21.0, 1e-9));
+        assert_eq!(Color::white().contrast(&Color::white()), 1.0);
+
+        let gray = Color::from_rgb_u32(0x777777);
+        assert!(close(gray.relative_luminance(), 0.1845, 1e-4));
+        assert!(close(gray.contrast(&Color::white()), 4.48, 0.005));
+        assert!(close(
+            Color::from_rgb_u32(0x00ff00).relative_luminance(),
+            0.7152,
+            1e-12
+        ));
+    }
+
+    #[test]
+    fn adjust() {
+        let c = Color::from_rgba(0x33, 0x66, 0x99, 0.5);
+        let (l, h) = (c.to_oklch().l, c.to_oklch().h);
+        let lighter = c.lighten(0.1).to_oklch();
+        assert!(close(lighter.l, l + 0.1, 0.005) && close(lighter.h, h, 1.0));
+        let darker = c.darken(0.1).to_oklch();
+        assert!(close(darker.l, l - 0.1, 0.005) && close(darker.h, h, 1.0));
+        assert_eq!(c.lighten(0.1).a, c.a);
+        assert_eq!(rgb(c.lighten(1.0)), 0xffffff);
+        assert_eq!(rgb(c.darken(1.0)), 0x000000);
+        assert_eq!(rgb(c.lighten(0.0)), rgb(c));
+
+        let gray = c.saturate(-1.0);
+        assert!(gray.to_oklch().c < 1e-3);
+        assert!(close(gray.to_oklch().l, l, 0.005));
+        assert!(c.saturate(0.5).to_oklch().c > c.to_oklch().c);
+        assert_eq!(rgb(c.saturate(0.0)), rgb(c));
+    }
+
+    #[test]
+    fn mix() {
+        let (a, b) = (
+            Color::from_rgb_u32(0xff0000),
+            Color::from_rgba(0, 0, 0xff, 0.0),
+        );
+        assert_eq!(rgb(a.mix(&b, 0.0)), rgb(a));
+        assert_eq!(rgb(a.mix(&b, 1.0)), rgb(b));
+        assert_eq!(a.mix(&b, 0.0).a, 0xff);
+        assert_eq!(a.mix(&b, 1.0).a, 0);
+        assert_eq!(a.mix(&b, 0.5).a, 128);
+
+        // halfway in OKLab is L 0.5, which is darker than halfway in sRGB.
+        let mid = Color::black().mix(&Color::white(), 0.5);
+        assert_eq!(rgb(mid), 0x636363);
+        let mid = a.mix(&b, 0.5).to_oklab();
+        let (p, q) = (a.to_oklab(), b.to_oklab());
+        assert!(close(mid.l, (p.l + q.l) / 2.0, 0.01));
+        assert!(close(mid.b, (p.b + q.b) / 2.0, 0.01));
+    }
+
+    #[test]
+    fn parse_functions() {
+        let c = |s: &str| s.parse::<Color>().map(|c| (c.to_rgb_u32(), c.a));
+        assert_eq!(c("rgb(255, 136, 0)"), Ok((0xff8800, 0xff)));
+        assert_eq!(c("rgb(100% 50% 0% / 50%)"), Ok((0xff8000, 0x80)));
+        assert_eq!(c("RGBA(0,0,0,0.5)"), Ok((0x000000, 0x80)));
+        assert_eq!(c("rgb(300, -5, 0)"), Ok((0xff0000, 0xff)));
+        assert_eq!(c("hsl(120, 100%, 50%)"), Ok((0x00ff00, 0xff)));
+        assert_eq!(c("hsl(120deg 100% 25%)"), Ok((0x008000, 0xff)));
+        assert_eq!(c("hsla(0, 0%, 100%, 0)"), Ok((0xffffff, 0)));
+        assert_eq!(c("oklch(62.8% 0.2577 29.23)"), Ok((0xff0000, 0xff)));
+        assert_eq!(c("oklch(0.628 64.4% 29.23deg)"), Ok((0xff0000, 0xff)));
+        for s in [
+            "rgb(1, 2)",
+            "rgb(1, 2, 3, 4, 5)",
+            "rgb(a, b, c)",
+            "cmyk(0, 0, 0)",
+            "rgb(1, 2, 3",
+        ] {
+            assert!(c(s).is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn parse_named() {
+        let c = |s: &str| s.parse::<Color>().map(|c| (c.to_rgb_u32(), c.a));
+        assert_eq!(c("rebeccapurple"), Ok((0x663399, 0xff)));
+        assert_eq!(c(" Red "), Ok((0xff0000, 0xff)));
+        assert_eq!(c("aliceblue"), Ok((0xf0f8ff, 0xff)));
+        assert_eq!(c("yellowgreen"), Ok((0x9acd32, 0xff)));
+        assert_eq!(c("transparent").map(|(_, a)| a), Ok(0));
+        assert!(c("bogus").is_err());
+        assert!(c("").is_err());
+        assert!(NAMED.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn parse_list() {
+        let colors = Color::parse_list("#123,rgb(1, 2, 3), hsl(0 0% 100%),black").unwrap();
+        assert_eq!(
+            colors.iter().map(Color::to_rgb_u32).collect::<Vec<_>>(),
+            vec![0x112233, 0x010203, 0xffffff, 0x000000]
+        );
+        assert_eq!(
+            Color::format_list(&colors),
+            "#112233,#010203,#ffffff,#000000"
+        );
+        assert!(Color::parse_list("#123,").is_err());
+    }
+}
@@ -1,44 +1,12 @@
-use crate::{a, b, c, d, e, RenderOpts};
-use cairo::Context;
 use chrono::Utc;
-use clap::{Subcommand, ValueEnum};
-use std::{error::Error, fmt::Display};
-
-#[derive(Subcommand, Debug)]
-pub enum Command {
-    A(a::Args),
-    B,
-    C,
-    D,
-    E(e::Args),
-}
-
-impl Command {
-    pub fn render(&self, args: &dyn RenderOpts, ctx: &Context) -> Result<(), Box<dyn Error>> {
-        match self {
-            Command::A(params) => a::render(args, ctx, params),
-            Command::B => b::render(args, ctx),
-            Command::C => c::render(args, ctx),
-            Command::D => d::render(args, ctx),
-            Command::E(params) => e::render(args, ctx, params),
-        }
-    }
-
-    pub fn name(&self) -> &str {
-        match self {
-            Command::A(_) => "a",
-            Command::B => "b",
-            Command::C => "c",
-            Command::D => "d",
-            Command::E(_) => "e",
-        }
-    }
-}
+use clap::ValueEnum;
+use std::fmt::Display;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
 pub enum Format {
     Png,
     Pdf,
+    Svg,
 }
 
 impl Format {
@@ -46,6 +14,7 @@ impl Format {
         match self {
             Format::Pdf => "pdf",
             Format::Png => "png",
+            Format::Svg => "svg",
         }
     }
 }
@@ -1,7 +1,15 @@
-use crate::{Color, RenderOpts};
+use crate::{
+    error::SketchError, grid::Grid, params::Param, path::Path2, sketch::Sketch, Color, Point, Rect,
+    RenderOpts, Tone,
+};
 use cairo::Context;
+use clap::ArgMatches;
 use rand::Rng;
-use std::error::Error;
+
+const NX: Param = Param::int("nx", 20..80).with_min(1.0);
+const NY: Param = Param::int("ny", 5..20).with_min(2.0);
+
+const PARAMS: &[Param] = &[NX, NY];
 
 struct Series {
     width: f64,
@@ -16,27 +24,39 @@ impl Series {
 }
 
 fn color_contrasting_with(c: &Color) -> Color {
-    if c.luminance() > 0.5 {
+    if Tone::of(c) == Tone::Light {
         Color::from_rgb(0x33, 0x33, 0x33)
     } else {
         Color::white()
     }
 }
 
-fn draw_path(ctx: &Context, pts: &[(f64, f64)], width: f64) -> Result<(), Box<dyn Error>> {
-    ctx.new_path();
-    let &(_, y) = pts.first().ok_or_else(|| String::from("empty path"))?;
-    ctx.move_to(0.0, y);
-    for &(x, y) in pts.iter() {
-        ctx.line_to(x, y);
+pub struct D;
+
+impl Sketch for D {
+    fn name(&self) -> &'static str {
+        "d"
     }
-    let &(_, y) = pts.last().unwrap();
-    ctx.line_to(width, y);
 
-    Ok(())
+    fn description(&self) -> &'static str {
+        "Two paths weaving through the columns of a grid"
+    }
+
+    fn params(&self) -> &'static [Param] {
+        PARAMS
+    }
+
+    fn render(
+        &self,
+        opts: &dyn RenderOpts,
+        ctx: &Context,
+        _: &ArgMatches,
+    ) -> Result<(), SketchError> {
+        render(opts, ctx)
+    }
 }
 
-pub fn render(opts: &dyn RenderOpts, ctx: &Context) -> Result<(), Box<dyn Error>> {
+pub fn render(opts: &dyn RenderOpts, ctx: &Context) -> Result<(), SketchError> {
     let size = opts.size();
     let width = size.width() as f64;
     let height = size.height() as f64;
@@ -53,10 +73,10 @@ pub fn render(opts: &dyn RenderOpts, ctx: &Context) -> Result<(), Box<dyn Error>
     ctx.fill()?;
     ctx.restore()?;
 
-    let nx = rng.gen_range(20..80);
-    let ny = rng.gen_range(5..20);
-    let dx = width / nx as f64;
-    let dy = height / ny as f64;
+    let params = opts.params();
+    let nx: i32 = params.int(&mut rng, &NX)?;
+    let ny: i32 = params.int(&mut rng, &NY)?;
+    let grid = Grid::new(Rect::from_wh(width, height), nx as usize, ny as usize);
 
     let c = color_contrasting_with(&theme[0]);
 
@@ -67,22 +87,32 @@ pub fn render(opts: &dyn RenderOpts, ctx: &Context) -> Result<(), Box<dyn Error>
         })
         .collect::<Vec<_>>();
 
-    let path_a = pairs
-        .iter()
-        .enumerate()
-        .map(|(i, &(y, _))| (i as f64 * dx + dx / 2.0, y as f64 * dy + dy / 2.0))
-        .collect::<Vec<_>>();
-
-    let path_b = pairs
-        .iter()
-        .enumerate()
-        .map(|(i, &(_, y))| (i as f64 * dx + dx / 2.0, y as f64 * dy + dy / 2.0))
-        .collect::<Vec<_>>();
+    // the paths run out to the left and right edges.
+    let across = |path: Path2| {
+        let (first, last) = (*path.first().unwrap(), *path.last().unwrap());
+        path.with_ends(Point::new(0.0, first.y()), Point::new(width, last.y()))
+    };
+
+    let path_a = across(
+        pairs
+            .iter()
+            .enumerate()
+            .map(|(i, &(y, _))| grid.point(i, y as usize))
+            .collect(),
+    );
+
+    let path_b = across(
+        pairs
+            .iter()
+            .enumerate()
+            .map(|(i, &(_, y))| grid.point(i, y as usize))
+            .collect(),
+    );
 
     ctx.save()?;
     ctx.new_path();
-    for i in 0..nx {
-        let x = i as f64 * dx + dx / 2.0;
+    for i in 0..grid.nx() {
+        let x = grid.point(i, 0).x();
         ctx.move_to(x, 0.0);
         ctx.line_to(x, height);
     }
@@ -92,8 +122,8 @@ pub fn render(opts: &dyn RenderOpts, ctx: &Context) -> Result<(), Box<dyn Error>
 
     ctx.save()?;
     ctx.new_path();
-    for i in 0..ny {
-        let y = i as f64 * dy + dy / 2.0;
+    for j in 0..grid.ny() {
+        let y = grid.point(0, j).y();
         ctx.move_to(0.0, y);
         ctx.line_to(width, y);
     }
@@ -104,41 +134,15 @@ pub fn render(opts: &dyn RenderOpts, ctx: &Context) -> Result<(), Box<dyn Error>
 
     ctx.save()?;
     ctx.set_line_width(4.0);
-    draw_path(ctx, &path_a, width)?;
+    ctx.new_path();
+    path_a.draw(ctx);
     theme[1].set(ctx);
     ctx.stroke()?;
     theme[2].set(ctx);
-    draw_path(ctx, &path_b, width)?;
+    ctx.new_path();
+    path_b.draw(ctx);
     ctx.stroke()?;
     ctx.restore()?;
 
-    // ctx.save()?;
-    // ctx.set_line_width(4.0);
-    // ctx.new_path();
-    // for (i, (ya, _)) in pairs.iter().enumerate() {
-    //     let x = i as f64 * dx + dx / 2.0;
-    //     let y = *ya as f64 * dy + dy / 2.0;
-    //     if i == 0 {
-    //         ctx.move_to(x, y);
-    //     } else {
-    //         ctx.line_to(x, y);
-    //     }
-    // }
-    // theme[1].set(ctx);
-    // ctx.stroke()?;
-    // ctx.new_path();
-    // for (i, (_, yb)) in pairs.iter().enumerate() {
-    //     let x = i as f64 * dx + dx / 2.0;
-    //     let y = *yb as f64 * dy + dy / 2.0;
-    //     if i == 0 {
-    //         ctx.move_to(x, y);
-    //     } else {
-    //         ctx.line_to(x, y);
-    //     }
-    // }
-    // theme[2].set(ctx);
-    // ctx.stroke()?;
-    // ctx.restore()?;
-
     Ok(())
 }
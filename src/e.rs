@@ -1,10 +1,24 @@
-use crate::{Color, RenderOpts};
+use crate::{
+    error::SketchError,
+    grid::Grid,
+    params::Param,
+    path::{Path2, Smoothing},
+    sketch::Sketch,
+    Color, Point, Rect, RenderOpts, Tone,
+};
 use cairo::{Context, LineCap};
+use clap::{ArgMatches, Args as _, FromArgMatches};
 use rand::Rng;
-use std::{error::Error, f64::consts::PI, ops::Range};
+use std::f64::consts::PI;
 
 const TAU: f64 = 2.0 * PI;
 
+const NX: Param = Param::int("nx", 20..40).with_min(1.0);
+const NY: Param = Param::int("ny", 5..20).with_min(2.0);
+const DENSITY: Param = Param::fixed("density", 0.25).with_min(0.0);
+
+const PARAMS: &[Param] = &[NX, NY, DENSITY];
+
 #[derive(Debug, clap::Args)]
 pub struct Args {
     #[arg(long, default_value_t = false)]
@@ -14,10 +28,41 @@ pub struct Args {
     show_halos: bool,
 }
 
+pub struct E;
+
+impl Sketch for E {
+    fn name(&self) -> &'static str {
+        "e"
+    }
+
+    fn description(&self) -> &'static str {
+        "Vertical lines threaded through colored nodes on a grid"
+    }
+
+    fn params(&self) -> &'static [Param] {
+        PARAMS
+    }
+
+    fn augment_args(&self, cmd: clap::Command) -> clap::Command {
+        Args::augment_args(cmd)
+    }
+
+    fn render(
+        &self,
+        opts: &dyn RenderOpts,
+        ctx: &Context,
+        args: &ArgMatches,
+    ) -> Result<(), SketchError> {
+        let args =
+            Args::from_arg_matches(args).map_err(|e| SketchError::invalid_args(self.name(), e))?;
+        render(opts, ctx, &args)
+    }
+}
+
 fn index_of_max(colors: &[Color]) -> usize {
     colors
         .iter()
-        .map(|c| c.luminance())
+        .map(|c| c.relative_luminance())
         .enumerate()
         .max_by(|(_, la), (_, lb)| la.total_cmp(lb))
         .unwrap()
@@ -27,7 +72,7 @@ fn index_of_max(colors: &[Color]) -> usize {
 fn index_of_min(colors: &[Color]) -> usize {
     colors
         .iter()
-        .map(|c| c.luminance())
+        .map(|c| c.relative_luminance())
         .enumerate()
         .min_by(|(_, la), (_, lb)| la.total_cmp(lb))
         .unwrap()
@@ -57,12 +102,12 @@ fn select_nodes(
     density: f64,
 ) -> Vec<Vec<(Color, usize)>> {
     let mut nodes = Vec::new();
-    for _ in grid.x_range() {
+    for _ in 0..grid.nx() {
         let mut picks = Vec::new();
         // we need to pick at least one
-        let j = rng.gen_range(0..grid.ny / 2);
+        let j = rng.gen_range(0..grid.ny() / 2);
         picks.push((colors[0], j));
-        for j in j + 1..grid.ny {
+        for j in j + 1..grid.ny() {
             if rng.gen::<f64>() < density {
                 picks.push((colors[picks.len() % colors.len()], j))
             }
@@ -72,61 +117,6 @@ fn select_nodes(
     nodes
 }
 
-struct Grid {
-    width: f64,
-    height: f64,
-    nx: usize,
-    ny: usize,
-    dx: f64,
-    dy: f64,
-}
-
-impl Grid {
-    fn new(width: f64, height: f64, nx: usize, ny: usize) -> Self {
-        Self {
-            width,
-            height,
-            nx,
-            ny,
-            dx: width / (nx + 1) as f64,
-            dy: height / (ny + 1) as f64,
-        }
-    }
-
-    fn x_range(&self) -> Range<usize> {
-        0..self.nx
-    }
-
-    fn y_range(&self) -> Range<usize> {
-        0..self.ny
-    }
-
-    fn x_of(&self, i: usize) -> f64 {
-        (i + 1) as f64 * self.dx
-    }
-
-    fn y_of(&self, j: usize) -> f64 {
-        (j + 1) as f64 * self.dy
-    }
-
-    fn render(&self, ctx: &Context) -> Result<(), Box<dyn Error>> {
-        ctx.new_path();
-        for i in 0..self.nx {
-            let x = self.x_of(i);
-            ctx.move_to(x, 0.0);
-            ctx.line_to(x, self.height);
-        }
-
-        for j in 0..self.ny {
-            let y = self.y_of(j);
-            ctx.move_to(0.0, y);
-            ctx.line_to(self.width, y);
-        }
-
-        Ok(())
-    }
-}
-
 fn pick<T>(rng: &mut dyn rand::RngCore, a: T, b: T) -> T {
     if rng.gen::<bool>() {
         a
@@ -135,94 +125,56 @@ fn pick<T>(rng: &mut dyn rand::RngCore, a: T, b: T) -> T {
     }
 }
 
-struct Path {
-    pts: Vec<(f64, f64)>,
-}
-
-impl Path {
-    #[allow(dead_code)]
-    fn draw(&self, ctx: &Context) {
-        let &(x, y) = self.pts.first().unwrap();
-        ctx.move_to(x, y);
-        for &(x, y) in &self.pts[1..] {
-            ctx.line_to(x, y);
-        }
-    }
-
-    fn draw_smooth(&self, ctx: &Context) {
-        let &(x, y) = self.pts.first().unwrap();
-        ctx.move_to(x, y);
-        for i in 1..self.pts.len() {
-            let (xa, ya) = self.pts[i - 1];
-            let (xb, yb) = self.pts[i];
-            if (xa - xb).abs() < 0.001 {
-                ctx.line_to(xb, yb);
-            } else {
-                let cy = (yb - ya) / 2.0;
-                ctx.curve_to(xa, ya + cy, xb, ya + cy, xb, yb);
-            }
-        }
-    }
-
-    fn first(&self) -> &(f64, f64) {
-        self.pts.first().unwrap()
-    }
-
-    fn last(&self) -> &(f64, f64) {
-        self.pts.last().unwrap()
-    }
-}
-
 fn build_vline(
     rng: &mut dyn rand::RngCore,
     grid: &Grid,
     r: f64,
     x: f64,
     nodes: &[(Color, usize)],
-) -> Path {
-    let dyh = grid.dy / 2.0;
+) -> Path2 {
+    let dyh = grid.dy() / 2.0;
 
     let mut pts = Vec::new();
     let mut cx = x + pick(rng, -r, r);
     let &(_, j) = nodes.first().unwrap();
     if j == 0 {
-        pts.push((cx, dyh));
-        pts.push((cx, grid.y_of(j)));
+        pts.push(Point::new(cx, dyh));
+        pts.push(Point::new(cx, grid.point(0, j).y()));
     } else {
-        let y = grid.y_of(j);
-        pts.push((x, dyh));
-        pts.push((x, y - grid.dy));
-        pts.push((cx, y));
+        let y = grid.point(0, j).y();
+        pts.push(Point::new(x, dyh));
+        pts.push(Point::new(x, y - grid.dy()));
+        pts.push(Point::new(cx, y));
     }
 
     for k in 1..nodes.len() {
         let (_, ja) = nodes[k - 1];
         let (_, jb) = nodes[k];
         if jb - ja == 1 {
-            pts.push((cx, grid.y_of(jb)));
+            pts.push(Point::new(cx, grid.point(0, jb).y()));
         } else {
-            let ya = grid.y_of(ja);
-            let yb = grid.y_of(jb);
-            pts.push((x, ya + grid.dy));
-            pts.push((x, yb - grid.dy));
+            let ya = grid.point(0, ja).y();
+            let yb = grid.point(0, jb).y();
+            pts.push(Point::new(x, ya + grid.dy()));
+            pts.push(Point::new(x, yb - grid.dy()));
             cx = x + pick(rng, -r, r);
-            pts.push((cx, yb));
+            pts.push(Point::new(cx, yb));
         }
     }
 
     let &(_, j) = nodes.last().unwrap();
-    if j == grid.ny - 1 {
-        pts.push((cx, grid.y_of(grid.ny - 1) + dyh));
+    if j == grid.ny() - 1 {
+        pts.push(Point::new(cx, grid.point(0, grid.ny() - 1).y() + dyh));
     } else {
-        let y = grid.y_of(j);
-        pts.push((x, y + grid.dy));
-        pts.push((x, grid.y_of(grid.ny - 1) + dyh));
+        let y = grid.point(0, j).y();
+        pts.push(Point::new(x, y + grid.dy()));
+        pts.push(Point::new(x, grid.point(0, grid.ny() - 1).y() + dyh));
     }
-    Path { pts }
+    Path2::new(pts).with_smoothing(Smoothing::VerticalEase)
 }
 
 fn shadow_over(base: &Color, alpha: f64) -> Color {
-    if base.luminance() > 0.5 {
+    if Tone::of(base) == Tone::Light {
         Color::black()
     } else {
         Color::white()
@@ -230,7 +182,7 @@ fn shadow_over(base: &Color, alpha: f64) -> Color {
     .with_alpha(alpha)
 }
 
-pub fn render(opts: &dyn RenderOpts, ctx: &Context, args: &Args) -> Result<(), Box<dyn Error>> {
+pub fn render(opts: &dyn RenderOpts, ctx: &Context, args: &Args) -> Result<(), SketchError> {
     let size = opts.size();
     let width = size.width() as f64;
     let height = size.height() as f64;
@@ -252,31 +204,35 @@ pub fn render(opts: &dyn RenderOpts, ctx: &Context, args: &Args) -> Result<(), B
     ctx.fill()?;
     ctx.restore()?;
 
-    let grid = Grid::new(width, height, rng.gen_range(20..40), rng.gen_range(5..20));
+    let params = opts.params();
+    let grid = Grid::new(
+        Rect::from_wh(width, height),
+        params.int(&mut rng, &NX)?,
+        params.int(&mut rng, &NY)?,
+    )
+    .with_cell_margin(0.5);
 
     if args.show_grid {
         ctx.save()?;
-        grid.render(ctx)?;
         cb.set(ctx);
-        ctx.set_dash(&[1.0, 4.0], 0.0);
-        ctx.set_line_width(1.0);
-        ctx.stroke()?;
+        grid.render(ctx)?;
         ctx.restore()?;
     }
 
-    let r = grid.dx.min(grid.dy);
+    let r = grid.dx().min(grid.dy());
     let ra = r / 5.0;
     let rb = r * 0.45;
-    let nodes = select_nodes(&mut rng, &grid, &colors, 0.25);
+    let density = params.float(&mut rng, &DENSITY)?;
+    let nodes = select_nodes(&mut rng, &grid, &colors, density);
     let paths = nodes
         .iter()
         .enumerate()
-        .map(|(i, nodes)| build_vline(&mut rng, &grid, rb, grid.x_of(i), nodes))
+        .map(|(i, nodes)| build_vline(&mut rng, &grid, rb, grid.point(i, 0).x(), nodes))
         .collect::<Vec<_>>();
 
     ctx.save()?;
     ctx.translate(shadow_dx, shadow_dy);
-    paths.iter().for_each(|p| p.draw_smooth(ctx));
+    paths.iter().for_each(|p| p.draw(ctx));
     ctx.set_line_width(4.0);
     ctx.set_line_cap(LineCap::Round);
     shadow_over(&ca, 0.2).set(ctx);
@@ -287,20 +243,16 @@ pub fn render(opts: &dyn RenderOpts, ctx: &Context, args: &Args) -> Result<(), B
     ctx.translate(shadow_dx, shadow_dy);
     shadow_over(&ca, 0.2).set(ctx);
     for path in paths.iter() {
-        let &(x, y) = path.first();
-        ctx.new_path();
-        ctx.arc(x, y, 4.0, 0.0, TAU);
-        ctx.fill()?;
-
-        let &(x, y) = path.last();
-        ctx.new_path();
-        ctx.arc(x, y, 4.0, 0.0, TAU);
-        ctx.fill()?;
+        for p in [path.first(), path.last()].into_iter().flatten() {
+            ctx.new_path();
+            ctx.arc(p.x(), p.y(), 4.0, 0.0, TAU);
+            ctx.fill()?;
+        }
     }
     ctx.restore()?;
 
     ctx.save()?;
-    paths.iter().for_each(|p| p.draw_smooth(ctx));
+    paths.iter().for_each(|p| p.draw(ctx));
     ctx.set_line_width(4.0);
     ctx.set_line_cap(LineCap::Round);
     cb.set(ctx);
@@ -311,15 +263,11 @@ pub fn render(opts: &dyn RenderOpts, ctx: &Context, args: &Args) -> Result<(), B
     ctx.save()?;
     cb.set(ctx);
     for path in paths.iter() {
-        let &(x, y) = path.first();
-        ctx.new_path();
-        ctx.arc(x, y, 4.0, 0.0, TAU);
-        ctx.fill()?;
-
-        let &(x, y) = path.last();
-        ctx.new_path();
-        ctx.arc(x, y, 4.0, 0.0, TAU);
-        ctx.fill()?;
+        for p in [path.first(), path.last()].into_iter().flatten() {
+            ctx.new_path();
+            ctx.arc(p.x(), p.y(), 4.0, 0.0, TAU);
+            ctx.fill()?;
+        }
     }
     ctx.restore()?;
 
@@ -327,10 +275,9 @@ pub fn render(opts: &dyn RenderOpts, ctx: &Context, args: &Args) -> Result<(), B
         ctx.save()?;
         for (i, nodes) in nodes.iter().enumerate() {
             for (_, j) in nodes.iter() {
-                let x = grid.x_of(i);
-                let y = grid.y_of(*j);
+                let p = grid.point(i, *j);
                 ctx.new_path();
-                ctx.arc(x, y, r, 0.0, TAU);
+                ctx.arc(p.x(), p.y(), r, 0.0, TAU);
                 shadow_over(&ca, 1.0).set(ctx);
                 ctx.set_line_width(2.0);
                 ctx.set_dash(&[1.0, 5.0], 0.0);
@@ -345,8 +292,9 @@ pub fn render(opts: &dyn RenderOpts, ctx: &Context, args: &Args) -> Result<(), B
     ctx.translate(shadow_dx + 1.0, shadow_dy + 1.0);
     for (i, nodes) in nodes.iter().enumerate() {
         for &(_, j) in nodes.iter() {
+            let p = grid.point(i, j);
             ctx.new_path();
-            ctx.arc(grid.x_of(i), grid.y_of(j), ra, 0.0, TAU);
+            ctx.arc(p.x(), p.y(), ra, 0.0, TAU);
             shadow_over(&ca, 0.2).set(ctx);
             ctx.fill()?;
         }
@@ -357,8 +305,8 @@ pub fn render(opts: &dyn RenderOpts, ctx: &Context, args: &Args) -> Result<(), B
     ctx.save()?;
     for (i, nodes) in nodes.iter().enumerate() {
         for (color, j) in nodes.iter() {
-            let x = grid.x_of(i);
-            let y = grid.y_of(*j);
+            let p = grid.point(i, *j);
+            let (x, y) = (p.x(), p.y());
 
             ctx.new_path();
             ctx.arc(x, y, ra, 0.0, TAU);
@@ -0,0 +1,148 @@
+use std::{error::Error, fmt, io, path::PathBuf};
+
+/// The ways that loading, rendering or saving a sketch can fail.
+#[derive(Debug)]
+pub enum SketchError {
+    /// The themes, from the file at `path` when there is one, could not be
+    /// read or are not valid.
+    Themes {
+        path: Option<PathBuf>,
+        source: io::Error,
+    },
+
+    /// The theme to pick was pinned to one that doesn't exist, or no theme
+    /// matches the filters it was picked with.
+    ThemeChoice(String),
+
+    /// The dest template could not be rendered into a file name.
+    Template {
+        template: String,
+        source: tinytemplate::error::Error,
+    },
+
+    /// The cairo surface for `path` could not be created or written.
+    Surface { path: PathBuf, source: cairo::Error },
+
+    /// Cairo failed while a sketch was drawing.
+    Cairo(cairo::Error),
+
+    /// Reading or writing `path` failed.
+    Io { path: PathBuf, source: io::Error },
+
+    /// A param of `sketch` was overridden with a bad value, or asked for in a
+    /// way that doesn't match its declaration.
+    InvalidParam {
+        sketch: String,
+        param: String,
+        reason: String,
+    },
+
+    /// The arguments given to `sketch` don't match the ones it declares.
+    InvalidArgs { sketch: String, source: clap::Error },
+
+    /// The render metadata in a file, at `path` when known, could not be read
+    /// or written.
+    Metadata {
+        path: Option<PathBuf>,
+        reason: String,
+    },
+}
+
+impl SketchError {
+    pub(crate) fn metadata<S: Into<String>>(reason: S) -> Self {
+        SketchError::Metadata {
+            path: None,
+            reason: reason.into(),
+        }
+    }
+
+    pub(crate) fn invalid_args(sketch: &str, source: clap::Error) -> Self {
+        SketchError::InvalidArgs {
+            sketch: sketch.to_owned(),
+            source,
+        }
+    }
+
+    /// Attaches `path` to a themes or metadata error that doesn't know where it
+    /// came from.
+    pub(crate) fn at(self, path: PathBuf) -> Self {
+        match self {
+            SketchError::Themes { path: None, source } => SketchError::Themes {
+                path: Some(path),
+                source,
+            },
+            SketchError::Metadata { path: None, reason } => SketchError::Metadata {
+                path: Some(path),
+                reason,
+            },
+            e => e,
+        }
+    }
+}
+
+impl fmt::Display for SketchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SketchError::Themes {
+                path: Some(path),
+                source,
+            } => write!(f, "cannot read themes from {}: {}", path.display(), source),
+            SketchError::Themes { path: None, source } => {
+                write!(f, "cannot read themes: {}", source)
+            }
+            SketchError::ThemeChoice(reason) => write!(f, "cannot pick a theme: {}", reason),
+            SketchError::Template { template, source } => {
+                write!(f, "invalid dest template {:?}: {}", template, source)
+            }
+            SketchError::Surface { path, source } => {
+                write!(
+                    f,
+                    "cannot create surface for {}: {}",
+                    path.display(),
+                    source
+                )
+            }
+            SketchError::Cairo(source) => write!(f, "drawing failed: {}", source),
+            SketchError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            SketchError::InvalidParam {
+                sketch,
+                param,
+                reason,
+            } => write!(
+                f,
+                "invalid param {} for sketch {}: {}",
+                param, sketch, reason
+            ),
+            SketchError::InvalidArgs { sketch, source } => {
+                write!(f, "invalid arguments for sketch {}: {}", sketch, source)
+            }
+            SketchError::Metadata {
+                path: Some(path),
+                reason,
+            } => write!(f, "bad metadata in {}: {}", path.display(), reason),
+            SketchError::Metadata { path: None, reason } => {
+                write!(f, "bad metadata: {}", reason)
+            }
+        }
+    }
+}
+
+impl Error for SketchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SketchError::Themes { source, .. } | SketchError::Io { source, .. } => Some(source),
+            SketchError::Template { source, .. } => Some(source),
+            SketchError::Surface { source, .. } | SketchError::Cairo(source) => Some(source),
+            SketchError::InvalidArgs { source, .. } => Some(source),
+            SketchError::ThemeChoice(_)
+            | SketchError::InvalidParam { .. }
+            | SketchError::Metadata { .. } => None,
+        }
+    }
+}
+
+impl From<cairo::Error> for SketchError {
+    fn from(e: cairo::Error) -> Self {
+        SketchError::Cairo(e)
+    }
+}
@@ -0,0 +1,391 @@
+use crate::{
+    error::SketchError,
+    grid::Grid,
+    noise::{Fractal, Noise, Simplex},
+    params::Param,
+    path::Path2,
+    sampling::jittered_grid,
+    sketch::Sketch,
+    Color, Point, Rect, RenderOpts,
+};
+use cairo::{Context, LineCap, LineJoin};
+use clap::{ArgMatches, Args as _, FromArgMatches};
+use rand::{seq::SliceRandom, Rng};
+use std::collections::VecDeque;
+
+const SCALE: Param = Param::float("scale", 1.5..4.0).with_min(0.1);
+const LINE_WIDTH: Param = Param::float("line-width", 1.0..3.0).with_min(0.0);
+
+const PARAMS: &[Param] = &[SCALE, LINE_WIDTH];
+
+// How close a streamline can come to others, as a fraction of the separation
+// at which new lines are started.
+const TEST_RATIO: f64 = 0.5;
+
+// The most steps a streamline takes in each direction from its seed.
+const MAX_STEPS: usize = 2000;
+
+// Streamlines shorter than this many separations are dropped.
+const MIN_LENGTH: f64 = 2.0;
+
+/// The vector field that the streamlines follow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Field {
+    /// Directions taken from fractal noise
+    Noise,
+    /// The curl of fractal noise, which swirls without converging
+    Curl,
+    /// A handful of vortices turning either way
+    Vortex,
+    /// Flow from a source to a sink
+    Dipole,
+    /// Rows of sine waves
+    Waves,
+}
+
+const FIELDS: &[Field] = &[
+    Field::Noise,
+    Field::Curl,
+    Field::Vortex,
+    Field::Dipole,
+    Field::Waves,
+];
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The most streamlines to draw
+    #[arg(long, default_value_t = 3000)]
+    lines: usize,
+
+    /// The distance a streamline moves in each step
+    #[arg(long, default_value_t = 2.0, value_parser = positive)]
+    step: f64,
+
+    /// The spacing at which new streamlines are started beside others
+    #[arg(long, default_value_t = 8.0, value_parser = positive)]
+    separation: f64,
+
+    /// The field to follow, picked at random if not given
+    #[arg(long, value_enum)]
+    field: Option<Field>,
+}
+
+fn positive(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(v) if v > 0.0 && v.is_finite() => Ok(v),
+        _ => Err(format!("expected a positive number, got {}", s)),
+    }
+}
+
+pub struct F;
+
+impl Sketch for F {
+    fn name(&self) -> &'static str {
+        "f"
+    }
+
+    fn description(&self) -> &'static str {
+        "Evenly spaced streamlines traced through a flow field"
+    }
+
+    fn params(&self) -> &'static [Param] {
+        PARAMS
+    }
+
+    fn augment_args(&self, cmd: clap::Command) -> clap::Command {
+        Args::augment_args(cmd)
+    }
+
+    fn render(
+        &self,
+        opts: &dyn RenderOpts,
+        ctx: &Context,
+        args: &ArgMatches,
+    ) -> Result<(), SketchError> {
+        let args =
+            Args::from_arg_matches(args).map_err(|e| SketchError::invalid_args(self.name(), e))?;
+        render(opts, ctx, &args)
+    }
+}
+
+// A vortex at `center`, which turns clockwise when `strength` is positive and
+// falls off outside of `radius`.
+#[derive(Debug)]
+struct Vortex {
+    center: Point,
+    strength: f64,
+    radius: f64,
+}
+
+// A field, in coordinates where the shorter side of the image is `scale`
+// units long.
+enum Flow {
+    Noise(Fractal<Simplex>),
+    Curl(Fractal<Simplex>),
+    Vortex(Vec<Vortex>, Point),
+    Dipole(Point, Point),
+    Waves(f64, f64),
+}
+
+impl Flow {
+    fn new(rng: &mut dyn rand::RngCore, field: Field, bounds: &Rect) -> Self {
+        let at = |rng: &mut dyn rand::RngCore| {
+            Point::new(
+                rng.gen_range(bounds.left()..bounds.right()),
+                rng.gen_range(bounds.top()..bounds.bottom()),
+            )
+        };
+        match field {
+            Field::Noise => Flow::Noise(Fractal::fbm(Simplex::new(rng), 3)),
+            Field::Curl => Flow::Curl(Fractal::fbm(Simplex::new(rng), 3)),
+            Field::Vortex => {
+                let n = rng.gen_range(3..7);
+                let vortices = (0..n)
+                    .map(|_| Vortex {
+                        center: at(rng),
+                        strength: if rng.gen::<bool>() { 1.0 } else { -1.0 },
+                        radius: rng.gen_range(0.2..0.6),
+                    })
+                    .collect();
+                let drift = Point::from_polar(0.2, rng.gen_range(0.0..std::f64::consts::TAU));
+                Flow::Vortex(vortices, drift)
+            }
+            Field::Dipole => Flow::Dipole(at(rng), at(rng)),
+            Field::Waves => Flow::Waves(rng.gen_range(2.0..5.0), rng.gen_range(0.3..1.0)),
+        }
+    }
+
+    // The direction of the field at `p`, which is zero where the field stops.
+    fn at(&self, p: &Point) -> Point {
+        let v = match self {
+            Flow::Noise(n) => Point::from_polar(1.0, n.at(p) * std::f64::consts::TAU),
+            Flow::Curl(n) => {
+                let h = 1e-3;
+                let dx = n.noise2(p.x() + h, p.y()) - n.noise2(p.x() - h, p.y());
+                let dy = n.noise2(p.x(), p.y() + h) - n.noise2(p.x(), p.y() - h);
+                Point::new(dy, -dx) / (2.0 * h)
+            }
+            Flow::Vortex(vortices, drift) => vortices.iter().fold(*drift, |v, vx| {
+                let d = *p - vx.center;
+                v + d.perp() * (vx.strength / (d.length_squared() + vx.radius * vx.radius))
+            }),
+            Flow::Dipole(source, sink) => {
+                let (a, b) = (*p - *source, *p - *sink);
+                a / a.length_squared().max(1e-6) - b / b.length_squared().max(1e-6)
+            }
+            Flow::Waves(k, a) => Point::new(1.0, a * (p.x() * k + (p.y() * k).sin()).sin()),
+        };
+        if v.length() < 1e-9 {
+            Point::zero()
+        } else {
+            v.normalize()
+        }
+    }
+}
+
+// The points of the streamlines so far, bucketed by cells as wide as the
+// largest distance that is looked up.
+struct Points {
+    bounds: Rect,
+    size: f64,
+    nx: usize,
+    ny: usize,
+    cells: Vec<Vec<(Point, usize, i64)>>,
+    // the indices of the cells that each streamline has points in.
+    lines: Vec<Vec<usize>>,
+}
+
+impl Points {
+    fn new(bounds: Rect, size: f64) -> Self {
+        let nx = (bounds.width() / size).ceil().max(1.0) as usize;
+        let ny = (bounds.height() / size).ceil().max(1.0) as usize;
+        Self {
+            bounds,
+            size,
+            nx,
+            ny,
+            cells: vec![Vec::new(); nx * ny],
+            lines: Vec::new(),
+        }
+    }
+
+    fn cell_of(&self, p: &Point) -> (usize, usize) {
+        let i = ((p.x() - self.bounds.left()) / self.size).max(0.0) as usize;
+        let j = ((p.y() - self.bounds.top()) / self.size).max(0.0) as usize;
+        (i.min(self.nx - 1), j.min(self.ny - 1))
+    }
+
+    // Adds the `k`th point of streamline `line`.
+    fn insert(&mut self, p: Point, line: usize, k: i64) {
+        let (i, j) = self.cell_of(&p);
+        let ix = j * self.nx + i;
+        self.cells[ix].push((p, line, k));
+        if self.lines.len() <= line {
+            self.lines.resize_with(line + 1, Vec::new);
+        }
+        // consecutive points mostly share a cell.
+        if self.lines[line].last() != Some(&ix) {
+            self.lines[line].push(ix);
+        }
+    }
+
+    fn remove(&mut self, line: usize) {
+        let Some(cells) = self.lines.get_mut(line) else {
+            return;
+        };
+        for ix in std::mem::take(cells) {
+            self.cells[ix].retain(|&(_, l, _)| l != line);
+        }
+    }
+
+    // Whether there are points within `r` of `p`, not counting those of
+    // streamline `line` that are fewer than `skip` steps from its `k`th point.
+    fn crowded(&self, p: &Point, r: f64, line: usize, k: i64, skip: i64) -> bool {
+        let (i, j) = self.cell_of(p);
+        let (i0, j0) = (i.saturating_sub(1), j.saturating_sub(1));
+        let (i1, j1) = ((i + 1).min(self.nx - 1), (j + 1).min(self.ny - 1));
+        (j0..=j1)
+            .flat_map(|j| (i0..=i1).map(move |i| (i, j)))
+            .flat_map(|(i, j)| self.cells[j * self.nx + i].iter())
+            .any(|&(q, l, n)| (l != line || (n - k).abs() >= skip) && q.distance(p) < r)
+    }
+}
+
+// Traces the streamline through `seed` in both directions, and keeps it if
+// it is long enough.
+fn trace(
+    flow: &Flow,
+    to_field: impl Fn(&Point) -> Point,
+    points: &mut Points,
+    seed: Point,
+    line: usize,
+    args: &Args,
+) -> Option<Vec<Point>> {
+    let bounds = points.bounds;
+    let d_test = args.separation * TEST_RATIO;
+    let skip = (d_test / args.step).ceil() as i64 + 1;
+    if !bounds.contains(&seed) || points.crowded(&seed, args.separation, line, 0, 0) {
+        return None;
+    }
+    points.insert(seed, line, 0);
+    let mut halves = [Vec::new(), Vec::new()];
+    for (half, dir) in halves.iter_mut().zip([1.0, -1.0]) {
+        let mut p = seed;
+        for n in 1..=MAX_STEPS as i64 {
+            // a midpoint step, in the direction of the field.
+            let v0 = flow.at(&to_field(&p)) * (dir * args.step);
+            let v = flow.at(&to_field(&(p + v0 / 2.0))) * (dir * args.step);
+            if v.length() < args.step / 2.0 {
+                break;
+            }
+            let q = p + v;
+            let k = n * dir as i64;
+            if !bounds.contains(&q) || points.crowded(&q, d_test, line, k, skip) {
+                break;
+            }
+            points.insert(q, line, k);
+            half.push(q);
+            p = q;
+        }
+    }
+    let [forward, backward] = halves;
+    let pts = backward
+        .into_iter()
+        .rev()
+        .chain(std::iter::once(seed))
+        .chain(forward)
+        .collect::<Vec<_>>();
+    if (pts.len() - 1) as f64 * args.step < MIN_LENGTH * args.separation {
+        points.remove(line);
+        return None;
+    }
+    Some(pts)
+}
+
+// Traces evenly spaced streamlines, after Jobard and Lefer, by seeding each
+// new line at the separation to either side of the points of earlier ones.
+// When those run out, lines are seeded from a jittered grid over the image.
+fn streamlines(
+    rng: &mut dyn rand::RngCore,
+    flow: &Flow,
+    to_field: impl Fn(&Point) -> Point,
+    bounds: &Rect,
+    args: &Args,
+) -> Vec<Vec<Point>> {
+    let mut points = Points::new(*bounds, args.separation);
+    let n = (bounds.width() / (4.0 * args.separation)).ceil() as usize;
+    let m = (bounds.height() / (4.0 * args.separation)).ceil() as usize;
+    let mut fallback = jittered_grid(rng, &Grid::new(*bounds, n.max(1), m.max(1)), 0.8);
+    fallback.shuffle(rng);
+
+    let mut lines: Vec<Vec<Point>> = Vec::new();
+    let mut queue = VecDeque::new();
+    while lines.len() < args.lines {
+        let seed = match queue.pop_front() {
+            Some(seed) => seed,
+            None => match fallback.pop() {
+                Some(seed) => seed,
+                None => break,
+            },
+        };
+        if let Some(pts) = trace(flow, &to_field, &mut points, seed, lines.len(), args) {
+            for w in pts.windows(2) {
+                let side = (w[1] - w[0]).normalize().perp() * args.separation;
+                queue.push_back(w[0] + side);
+                queue.push_back(w[0] - side);
+            }
+            lines.push(pts);
+        }
+    }
+    lines
+}
+
+pub fn render(opts: &dyn RenderOpts, ctx: &Context, args: &Args) -> Result<(), SketchError> {
+    let size = opts.size();
+    let width = size.width() as f64;
+    let height = size.height() as f64;
+
+    let mut rng = opts.rng();
+
+    let themes = opts.themes()?;
+    let (_, theme) = themes.pick(&mut rng);
+    let bg = theme[0];
+    let colors = &theme[1..];
+
+    ctx.save()?;
+    ctx.new_path();
+    ctx.rectangle(0.0, 0.0, width, height);
+    bg.set(ctx);
+    ctx.fill()?;
+    ctx.restore()?;
+
+    let params = opts.params();
+    let scale = params.float(&mut rng, &SCALE)?;
+    let lw = params.float(&mut rng, &LINE_WIDTH)?;
+
+    // the field is drawn even when it's given, so that giving it leaves the
+    // rest of the image as it was.
+    let picked = *FIELDS.choose(&mut rng).unwrap();
+    let field = args.field.unwrap_or(picked);
+    let unit = width.min(height) / scale;
+    let to_field = |p: &Point| *p / unit;
+    let flow = Flow::new(&mut rng, field, &Rect::from_wh(width / unit, height / unit));
+
+    let bounds = Rect::from_wh(width, height);
+    let lines = streamlines(&mut rng, &flow, to_field, &bounds, args);
+
+    ctx.save()?;
+    ctx.set_line_cap(LineCap::Round);
+    ctx.set_line_join(LineJoin::Round);
+    ctx.set_line_width(lw.min(args.separation * TEST_RATIO));
+    for pts in lines {
+        let color: &Color = colors.choose(&mut rng).unwrap_or(&bg);
+        color.set(ctx);
+        ctx.new_path();
+        Path2::new(pts).draw(ctx);
+        ctx.stroke()?;
+    }
+    ctx.restore()?;
+
+    Ok(())
+}
@@ -0,0 +1,339 @@
+use crate::{Point, Rect};
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+impl Point {
+    pub fn zero() -> Self {
+        Self::new(0.0, 0.0)
+    }
+
+    /// The point at distance `r` from the origin in the direction `angle`, in
+    /// radians. Since y grows downward, positive angles turn clockwise.
+    pub fn from_polar(r: f64, angle: f64) -> Self {
+        Self::new(r * angle.cos(), r * angle.sin())
+    }
+
+    pub fn dot(&self, other: &Point) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The z component of the 3D cross product, which is positive when `other`
+    /// is clockwise from this one.
+    pub fn cross(&self, other: &Point) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn length(&self) -> f64 {
+        self.x.hypot(self.y)
+    }
+
+    pub fn length_squared(&self) -> f64 {
+        self.dot(self)
+    }
+
+    pub fn distance(&self, other: &Point) -> f64 {
+        (*other - *self).length()
+    }
+
+    /// The angle of this point from the origin, in radians.
+    pub fn angle(&self) -> f64 {
+        self.y.atan2(self.x)
+    }
+
+    /// This point scaled to a length of 1. The zero point stays as it is.
+    pub fn normalize(&self) -> Self {
+        let len = self.length();
+        if len == 0.0 {
+            *self
+        } else {
+            *self / len
+        }
+    }
+
+    /// The point `t` of the way from this one to `other`.
+    pub fn lerp(&self, other: &Point, t: f64) -> Self {
+        *self + (*other - *self) * t
+    }
+
+    /// This point turned a quarter turn clockwise about the origin.
+    pub fn perp(&self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    /// This point turned by `angle`, in radians, about the origin.
+    pub fn rotate(&self, angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    /// This point turned by `angle`, in radians, about `origin`.
+    pub fn rotate_about(&self, origin: &Point, angle: f64) -> Self {
+        (*self - *origin).rotate(angle) + *origin
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, rhs: Point) -> Point {
+        Point::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, rhs: Point) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, rhs: Point) -> Point {
+        Point::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, rhs: Point) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<f64> for Point {
+    type Output = Point;
+
+    fn mul(self, rhs: f64) -> Point {
+        Point::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Mul<Point> for f64 {
+    type Output = Point;
+
+    fn mul(self, rhs: Point) -> Point {
+        rhs * self
+    }
+}
+
+impl Div<f64> for Point {
+    type Output = Point;
+
+    fn div(self, rhs: f64) -> Point {
+        Point::new(self.x / rhs, self.y / rhs)
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Point {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl From<(f64, f64)> for Point {
+    fn from((x, y): (f64, f64)) -> Self {
+        Point::new(x, y)
+    }
+}
+
+impl Rect {
+    pub fn center(&self) -> Point {
+        self.tl.lerp(&self.br, 0.5)
+    }
+
+    pub fn top_right(&self) -> Point {
+        Point::new(self.br.x, self.tl.y)
+    }
+
+    pub fn bottom_left(&self) -> Point {
+        Point::new(self.tl.x, self.br.y)
+    }
+
+    /// The rect moved in from each side by `d`. A negative `d` moves the sides
+    /// out, as `outset` does.
+    pub fn inset(&self, d: f64) -> Self {
+        self.inset_xy(d, d)
+    }
+
+    /// The rect moved in from the left and right by `dx` and from the top and
+    /// bottom by `dy`.
+    pub fn inset_xy(&self, dx: f64, dy: f64) -> Self {
+        Self::from_ltrb(
+            self.left() + dx,
+            self.top() + dy,
+            self.right() - dx,
+            self.bottom() - dy,
+        )
+    }
+
+    pub fn outset(&self, d: f64) -> Self {
+        self.inset(-d)
+    }
+
+    pub fn translate(&self, d: &Point) -> Self {
+        Self::new(&(self.tl + *d), &(self.br + *d))
+    }
+
+    /// Whether `p` is inside the rect. Points on the left and top edges are
+    /// inside and those on the right and bottom are not, so that the rects
+    /// from `subdivide` don't overlap.
+    pub fn contains(&self, p: &Point) -> bool {
+        p.x >= self.left() && p.x < self.right() && p.y >= self.top() && p.y < self.bottom()
+    }
+
+    /// The overlap of the two rects, or `None` if they don't overlap.
+    pub fn intersect(&self, other: &Rect) -> Option<Self> {
+        let l = self.left().max(other.left());
+        let t = self.top().max(other.top());
+        let r = self.right().min(other.right());
+        let b = self.bottom().min(other.bottom());
+        if l < r && t < b {
+            Some(Self::from_ltrb(l, t, r, b))
+        } else {
+            None
+        }
+    }
+
+    /// The smallest rect that contains both rects.
+    pub fn union(&self, other: &Rect) -> Self {
+        Self::from_ltrb(
+            self.left().min(other.left()),
+            self.top().min(other.top()),
+            self.right().max(other.right()),
+            self.bottom().max(other.bottom()),
+        )
+    }
+
+    /// The largest rect with the given width to height ratio that fits in this
+    /// one, centered in it.
+    pub fn fit_aspect(&self, aspect: f64) -> Self {
+        let (w, h) = if self.width() / self.height() > aspect {
+            (self.height() * aspect, self.height())
+        } else {
+            (self.width(), self.width() / aspect)
+        };
+        let c = self.center();
+        Self::from_xywh(c.x - w / 2.0, c.y - h / 2.0, w, h)
+    }
+
+    /// Splits the rect into `nx` columns and `ny` rows of equal cells, which
+    /// are returned row by row.
+    pub fn subdivide(&self, nx: usize, ny: usize) -> Vec<Self> {
+        let dx = self.width() / nx as f64;
+        let dy = self.height() / ny as f64;
+        (0..ny)
+            .flat_map(|j| {
+                (0..nx).map(move |i| {
+                    Self::from_xywh(
+                        self.left() + dx * i as f64,
+                        self.top() + dy * j as f64,
+                        dx,
+                        dy,
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    fn close(a: Point, b: Point) -> bool {
+        a.distance(&b) < 1e-9
+    }
+
+    #[test]
+    fn point_math() {
+        let (a, b) = (Point::new(3.0, 4.0), Point::new(-1.0, 2.0));
+        assert_eq!(a.length(), 5.0);
+        assert_eq!(a.length_squared(), 25.0);
+        assert_eq!(a.dot(&b), 5.0);
+        assert_eq!(a.cross(&b), 10.0);
+        assert_eq!(a.lerp(&b, 0.5), Point::new(1.0, 3.0));
+        assert!(close(a.normalize(), Point::new(0.6, 0.8)));
+        assert_eq!(Point::zero().normalize(), Point::zero());
+        // with y down, a quarter turn takes +x to +y, which is clockwise.
+        assert_eq!(Point::new(1.0, 0.0).perp(), Point::new(0.0, 1.0));
+        assert!(close(
+            Point::new(1.0, 0.0).rotate(FRAC_PI_2),
+            Point::new(0.0, 1.0)
+        ));
+        assert!(close(
+            Point::from_polar(2.0, FRAC_PI_2),
+            Point::new(0.0, 2.0)
+        ));
+        assert!(close(
+            Point::new(2.0, 1.0).rotate_about(&Point::new(1.0, 1.0), FRAC_PI_2),
+            Point::new(1.0, 2.0)
+        ));
+    }
+
+    #[test]
+    fn inset() {
+        let r = Rect::from_xywh(10.0, 20.0, 100.0, 50.0);
+        assert_eq!(r.inset(5.0), Rect::from_ltrb(15.0, 25.0, 105.0, 65.0));
+        assert_eq!(
+            r.inset_xy(10.0, 0.0),
+            Rect::from_ltrb(20.0, 20.0, 100.0, 70.0)
+        );
+        assert_eq!(r.outset(5.0), r.inset(-5.0));
+        assert_eq!(r.inset(5.0).center(), r.center());
+        assert_eq!(r.inset(0.0), r);
+    }
+
+    #[test]
+    fn intersect() {
+        let a = Rect::from_wh(10.0, 10.0);
+        let b = Rect::from_xywh(5.0, -5.0, 10.0, 10.0);
+        assert_eq!(a.intersect(&b), Some(Rect::from_ltrb(5.0, 0.0, 10.0, 5.0)));
+        assert_eq!(a.intersect(&a), Some(a));
+        // apart, and touching along an edge, are both no overlap.
+        assert_eq!(a.intersect(&Rect::from_xywh(20.0, 0.0, 5.0, 5.0)), None);
+        assert_eq!(a.intersect(&Rect::from_xywh(10.0, 0.0, 5.0, 5.0)), None);
+        assert_eq!(a.intersect(&Rect::from_xywh(0.0, 10.0, 5.0, 5.0)), None);
+        assert_eq!(a.union(&b), Rect::from_ltrb(0.0, -5.0, 15.0, 10.0));
+    }
+
+    #[test]
+    fn fit_aspect() {
+        let wide = Rect::from_xywh(0.0, 0.0, 200.0, 100.0);
+        assert_eq!(
+            wide.fit_aspect(1.0),
+            Rect::from_xywh(50.0, 0.0, 100.0, 100.0)
+        );
+        assert_eq!(
+            wide.fit_aspect(4.0),
+            Rect::from_xywh(0.0, 25.0, 200.0, 50.0)
+        );
+        assert_eq!(wide.fit_aspect(2.0), wide);
+        let fit = Rect::from_xywh(-10.0, 5.0, 30.0, 90.0).fit_aspect(0.5);
+        assert_eq!((fit.width(), fit.height()), (30.0, 60.0));
+        assert_eq!(fit.center(), Point::new(5.0, 50.0));
+    }
+
+    #[test]
+    fn subdivide() {
+        let r = Rect::from_xywh(10.0, 10.0, 90.0, 40.0);
+        let cells = r.subdivide(3, 2);
+        assert_eq!(cells.len(), 6);
+        assert_eq!(cells[0], Rect::from_xywh(10.0, 10.0, 30.0, 20.0));
+        // row by row, so the fourth cell starts the second row.
+        assert_eq!(cells[3], Rect::from_xywh(10.0, 30.0, 30.0, 20.0));
+        assert_eq!(cells[5].bottom_right(), r.bottom_right());
+        // every point is in exactly one cell.
+        for p in [
+            r.center(),
+            *r.top_left(),
+            Point::new(40.0, 30.0),
+            Point::new(99.9, 49.9),
+        ] {
+            assert_eq!(cells.iter().filter(|c| c.contains(&p)).count(), 1, "{}", p);
+        }
+        assert!(!r.contains(r.bottom_right()));
+        assert!(r.subdivide(0, 2).is_empty());
+    }
+}
@@ -0,0 +1,905 @@
+use crate::Point;
+use std::ops::{Add, AddAssign, Div, Index, Mul, Neg, Sub, SubAssign};
+
+/// A point or direction in a right-handed world with y up. A `Projection`
+/// turns it into page coordinates, where y grows downward.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Vec3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Vec3 {
+    pub const fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    pub const fn zero() -> Self {
+        Self::new(0.0, 0.0, 0.0)
+    }
+
+    pub fn dot(&self, other: &Vec3) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(&self, other: &Vec3) -> Self {
+        Self::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn length(&self) -> f64 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn length_squared(&self) -> f64 {
+        self.dot(self)
+    }
+
+    pub fn distance(&self, other: &Vec3) -> f64 {
+        (*other - *self).length()
+    }
+
+    /// This vector scaled to a length of 1. The zero vector stays as it is.
+    pub fn normalize(&self) -> Self {
+        let len = self.length();
+        if len == 0.0 {
+            *self
+        } else {
+            *self / len
+        }
+    }
+
+    /// The vector `t` of the way from this one to `other`.
+    pub fn lerp(&self, other: &Vec3, t: f64) -> Self {
+        *self + (*other - *self) * t
+    }
+
+    /// This point turned by `angle`, in radians, about the line through
+    /// `origin` parallel to the x axis.
+    pub fn rotate_x(&self, origin: &Vec3, angle: f64) -> Self {
+        Mat3::rotation_x(angle) * (*self - *origin) + *origin
+    }
+
+    /// As `rotate_x`, but about the y axis.
+    pub fn rotate_y(&self, origin: &Vec3, angle: f64) -> Self {
+        Mat3::rotation_y(angle) * (*self - *origin) + *origin
+    }
+
+    /// As `rotate_x`, but about the z axis.
+    pub fn rotate_z(&self, origin: &Vec3, angle: f64) -> Self {
+        Mat3::rotation_z(angle) * (*self - *origin) + *origin
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Vec3;
+
+    fn add(self, rhs: Vec3) -> Vec3 {
+        Vec3::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl AddAssign for Vec3 {
+    fn add_assign(&mut self, rhs: Vec3) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Vec3;
+
+    fn sub(self, rhs: Vec3) -> Vec3 {
+        Vec3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl SubAssign for Vec3 {
+    fn sub_assign(&mut self, rhs: Vec3) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<f64> for Vec3 {
+    type Output = Vec3;
+
+    fn mul(self, rhs: f64) -> Vec3 {
+        Vec3::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl Mul<Vec3> for f64 {
+    type Output = Vec3;
+
+    fn mul(self, rhs: Vec3) -> Vec3 {
+        rhs * self
+    }
+}
+
+impl Div<f64> for Vec3 {
+    type Output = Vec3;
+
+    fn div(self, rhs: f64) -> Vec3 {
+        Vec3::new(self.x / rhs, self.y / rhs, self.z / rhs)
+    }
+}
+
+impl Neg for Vec3 {
+    type Output = Vec3;
+
+    fn neg(self) -> Vec3 {
+        Vec3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl std::fmt::Display for Vec3 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({:0.3}, {:0.3}, {:0.3})", self.x, self.y, self.z)
+    }
+}
+
+/// A 3x3 matrix, stored by rows. Matrices multiply column vectors, so `a * b`
+/// applies `b` first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat3 {
+    rows: [[f64; 3]; 3],
+}
+
+impl Mat3 {
+    pub const fn from_rows(rows: [[f64; 3]; 3]) -> Self {
+        Self { rows }
+    }
+
+    pub const fn identity() -> Self {
+        Self::from_rows([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]])
+    }
+
+    pub fn scale(sx: f64, sy: f64, sz: f64) -> Self {
+        Self::from_rows([[sx, 0.0, 0.0], [0.0, sy, 0.0], [0.0, 0.0, sz]])
+    }
+
+    /// A rotation by `angle`, in radians, about the x axis. Positive angles
+    /// turn counterclockwise when looking from +x toward the origin, as with
+    /// all of the rotations.
+    pub fn rotation_x(angle: f64) -> Self {
+        let (s, c) = angle.sin_cos();
+        Self::from_rows([[1.0, 0.0, 0.0], [0.0, c, -s], [0.0, s, c]])
+    }
+
+    pub fn rotation_y(angle: f64) -> Self {
+        let (s, c) = angle.sin_cos();
+        Self::from_rows([[c, 0.0, s], [0.0, 1.0, 0.0], [-s, 0.0, c]])
+    }
+
+    pub fn rotation_z(angle: f64) -> Self {
+        let (s, c) = angle.sin_cos();
+        Self::from_rows([[c, -s, 0.0], [s, c, 0.0], [0.0, 0.0, 1.0]])
+    }
+
+    /// A rotation by `angle`, in radians, about `axis`.
+    pub fn rotation(axis: &Vec3, angle: f64) -> Self {
+        Quat::from_axis_angle(axis, angle).to_mat3()
+    }
+
+    /// The rotation that turns the world so that a viewer in the direction of
+    /// `azimuth` and `elevation`, in radians, looks down the -z axis with y
+    /// up. The viewer is in front, on the +z axis, at an azimuth of 0 and
+    /// turns toward +x as the azimuth grows, and looks down from above as the
+    /// elevation grows.
+    pub fn view(azimuth: f64, elevation: f64) -> Self {
+        let (sa, ca) = azimuth.sin_cos();
+        let (se, ce) = elevation.sin_cos();
+        Self::from_rows([
+            [ca, 0.0, -sa],
+            [-se * sa, ce, -se * ca],
+            [ce * sa, se, ce * ca],
+        ])
+    }
+
+    /// The view used by isometric projections, in which the x, y and z axes
+    /// are equally foreshortened.
+    pub fn isometric() -> Self {
+        Self::view(std::f64::consts::FRAC_PI_4, (1.0 / 3f64.sqrt()).asin())
+    }
+
+    /// The view used by dimetric projections, in which the x and z axes are
+    /// equally foreshortened and seen from `elevation`, in radians, above the
+    /// horizon.
+    pub fn dimetric(elevation: f64) -> Self {
+        Self::view(std::f64::consts::FRAC_PI_4, elevation)
+    }
+
+    pub fn row(&self, i: usize) -> Vec3 {
+        let [x, y, z] = self.rows[i];
+        Vec3::new(x, y, z)
+    }
+
+    pub fn col(&self, i: usize) -> Vec3 {
+        Vec3::new(self.rows[0][i], self.rows[1][i], self.rows[2][i])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let r = &self.rows;
+        Self::from_rows([
+            [r[0][0], r[1][0], r[2][0]],
+            [r[0][1], r[1][1], r[2][1]],
+            [r[0][2], r[1][2], r[2][2]],
+        ])
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.row(0).dot(&self.row(1).cross(&self.row(2)))
+    }
+
+    /// The inverse of the matrix, or `None` if it is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det.abs() < f64::EPSILON {
+            return None;
+        }
+        // the columns of the inverse are the cross products of the rows.
+        let (a, b, c) = (self.row(0), self.row(1), self.row(2));
+        let (x, y, z) = (b.cross(&c) / det, c.cross(&a) / det, a.cross(&b) / det);
+        Some(Self::from_rows([
+            [x.x, y.x, z.x],
+            [x.y, y.y, z.y],
+            [x.z, y.z, z.z],
+        ]))
+    }
+}
+
+impl Index<(usize, usize)> for Mat3 {
+    type Output = f64;
+
+    fn index(&self, (row, col): (usize, usize)) -> &f64 {
+        &self.rows[row][col]
+    }
+}
+
+impl Mul<Vec3> for Mat3 {
+    type Output = Vec3;
+
+    fn mul(self, v: Vec3) -> Vec3 {
+        Vec3::new(
+            self.row(0).dot(&v),
+            self.row(1).dot(&v),
+            self.row(2).dot(&v),
+        )
+    }
+}
+
+impl Mul for Mat3 {
+    type Output = Mat3;
+
+    fn mul(self, rhs: Mat3) -> Mat3 {
+        let mut rows = [[0.0; 3]; 3];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = self.row(i).dot(&rhs.col(j));
+            }
+        }
+        Mat3::from_rows(rows)
+    }
+}
+
+/// A 4x4 matrix for affine and projective transforms of homogeneous
+/// coordinates, stored by rows. As with `Mat3`, `a * b` applies `b` first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    rows: [[f64; 4]; 4],
+}
+
+impl Mat4 {
+    pub const fn from_rows(rows: [[f64; 4]; 4]) -> Self {
+        Self { rows }
+    }
+
+    pub const fn identity() -> Self {
+        Self::from_rows([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn translation(d: &Vec3) -> Self {
+        Self::from_rows([
+            [1.0, 0.0, 0.0, d.x],
+            [0.0, 1.0, 0.0, d.y],
+            [0.0, 0.0, 1.0, d.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scale(sx: f64, sy: f64, sz: f64) -> Self {
+        Mat3::scale(sx, sy, sz).into()
+    }
+
+    /// The transform from world coordinates to those of a camera at `eye`
+    /// looking at `target`, in which the camera looks down -z with `up`
+    /// pointing as close to +y as it can.
+    pub fn look_at(eye: &Vec3, target: &Vec3, up: &Vec3) -> Self {
+        let back = (*eye - *target).normalize();
+        let right = up.cross(&back).normalize();
+        let up = back.cross(&right);
+        let rotation: Mat4 = Mat3::from_rows([
+            [right.x, right.y, right.z],
+            [up.x, up.y, up.z],
+            [back.x, back.y, back.z],
+        ])
+        .into();
+        rotation * Mat4::translation(&-*eye)
+    }
+
+    /// An orthographic projection of the box between the planes at `near`
+    /// and `far` in front of the camera onto the cube from -1 to 1.
+    pub fn orthographic(left: f64, right: f64, bottom: f64, top: f64, near: f64, far: f64) -> Self {
+        let (w, h, d) = (right - left, top - bottom, far - near);
+        Self::from_rows([
+            [2.0 / w, 0.0, 0.0, -(right + left) / w],
+            [0.0, 2.0 / h, 0.0, -(top + bottom) / h],
+            [0.0, 0.0, -2.0 / d, -(far + near) / d],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// A perspective projection with a vertical field of view of `fov_y`, in
+    /// radians, of the frustum between `near` and `far` onto the cube from -1
+    /// to 1.
+    pub fn perspective(fov_y: f64, aspect: f64, near: f64, far: f64) -> Self {
+        let f = 1.0 / (fov_y / 2.0).tan();
+        let d = near - far;
+        Self::from_rows([
+            [f / aspect, 0.0, 0.0, 0.0],
+            [0.0, f, 0.0, 0.0],
+            [0.0, 0.0, (far + near) / d, 2.0 * far * near / d],
+            [0.0, 0.0, -1.0, 0.0],
+        ])
+    }
+
+    /// Transforms `p` as a point, dividing through by w.
+    pub fn transform_point(&self, p: &Vec3) -> Vec3 {
+        let r = &self.rows;
+        let v = [p.x, p.y, p.z, 1.0];
+        let dot = |row: &[f64; 4]| row.iter().zip(v.iter()).map(|(a, b)| a * b).sum::<f64>();
+        let w = dot(&r[3]);
+        Vec3::new(dot(&r[0]), dot(&r[1]), dot(&r[2])) / w
+    }
+
+    /// Transforms `v` as a direction, which leaves out the translation.
+    pub fn transform_vector(&self, v: &Vec3) -> Vec3 {
+        let r = &self.rows;
+        Vec3::new(
+            r[0][0] * v.x + r[0][1] * v.y + r[0][2] * v.z,
+            r[1][0] * v.x + r[1][1] * v.y + r[1][2] * v.z,
+            r[2][0] * v.x + r[2][1] * v.y + r[2][2] * v.z,
+        )
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut rows = [[0.0; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = self.rows[j][i];
+            }
+        }
+        Self::from_rows(rows)
+    }
+}
+
+impl From<Mat3> for Mat4 {
+    fn from(m: Mat3) -> Self {
+        let r = &m.rows;
+        Self::from_rows([
+            [r[0][0], r[0][1], r[0][2], 0.0],
+            [r[1][0], r[1][1], r[1][2], 0.0],
+            [r[2][0], r[2][1], r[2][2], 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+}
+
+impl Index<(usize, usize)> for Mat4 {
+    type Output = f64;
+
+    fn index(&self, (row, col): (usize, usize)) -> &f64 {
+        &self.rows[row][col]
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, rhs: Mat4) -> Mat4 {
+        let mut rows = [[0.0; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = (0..4).map(|k| self.rows[i][k] * rhs.rows[k][j]).sum();
+            }
+        }
+        Mat4::from_rows(rows)
+    }
+}
+
+/// A quaternion. Unit quaternions are rotations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quat {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quat {
+    pub const fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Self { w, x, y, z }
+    }
+
+    pub const fn identity() -> Self {
+        Self::new(1.0, 0.0, 0.0, 0.0)
+    }
+
+    /// The rotation by `angle`, in radians, about `axis`, which need not be a
+    /// unit vector.
+    pub fn from_axis_angle(axis: &Vec3, angle: f64) -> Self {
+        let (s, c) = (angle / 2.0).sin_cos();
+        let a = axis.normalize() * s;
+        Self::new(c, a.x, a.y, a.z)
+    }
+
+    pub fn conjugate(&self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    pub fn length(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Self {
+        let len = self.length();
+        if len == 0.0 {
+            Self::identity()
+        } else {
+            Self::new(self.w / len, self.x / len, self.y / len, self.z / len)
+        }
+    }
+
+    pub fn dot(&self, other: &Quat) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// Rotates `v` by this quaternion, which should be a unit quaternion.
+    pub fn rotate(&self, v: &Vec3) -> Vec3 {
+        let q = Vec3::new(self.x, self.y, self.z);
+        let t = q.cross(v) * 2.0;
+        *v + t * self.w + q.cross(&t)
+    }
+
+    pub fn to_mat3(&self) -> Mat3 {
+        let Quat { w, x, y, z } = self.normalize();
+        Mat3::from_rows([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ])
+    }
+
+    /// The rotation `t` of the way from this one to `other` along the
+    /// shortest arc.
+    pub fn slerp(&self, other: &Quat, t: f64) -> Self {
+        let mut d = self.dot(other);
+        // q and -q are the same rotation, and the one nearer to self is the
+        // short way around.
+        let other = if d < 0.0 {
+            d = -d;
+            Quat::new(-other.w, -other.x, -other.y, -other.z)
+        } else {
+            *other
+        };
+        let (a, b) = if d > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = d.acos();
+            let s = theta.sin();
+            (((1.0 - t) * theta).sin() / s, (t * theta).sin() / s)
+        };
+        Quat::new(
+            a * self.w + b * other.w,
+            a * self.x + b * other.x,
+            a * self.y + b * other.y,
+            a * self.z + b * other.z,
+        )
+        .normalize()
+    }
+}
+
+impl Mul for Quat {
+    type Output = Quat;
+
+    /// The rotation of `rhs` followed by that of `self`.
+    fn mul(self, rhs: Quat) -> Quat {
+        Quat::new(
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+        )
+    }
+}
+
+/// A polygon in 3D. Its front is the side from which its vertices run
+/// counterclockwise.
+#[derive(Debug, Clone)]
+pub struct Face {
+    pub vertices: Vec<Vec3>,
+}
+
+impl Face {
+    pub fn new(vertices: Vec<Vec3>) -> Self {
+        Self { vertices }
+    }
+
+    /// The unit normal on the front of the face, found with Newell's method so
+    /// that it's well defined for faces that aren't quite flat.
+    pub fn normal(&self) -> Vec3 {
+        let vs = &self.vertices;
+        vs.iter()
+            .zip(vs.iter().cycle().skip(1))
+            .fold(Vec3::zero(), |n, (a, b)| {
+                n + Vec3::new(
+                    (a.y - b.y) * (a.z + b.z),
+                    (a.z - b.z) * (a.x + b.x),
+                    (a.x - b.x) * (a.y + b.y),
+                )
+            })
+            .normalize()
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        let n = self.vertices.len().max(1) as f64;
+        self.vertices.iter().fold(Vec3::zero(), |c, &v| c + v) / n
+    }
+}
+
+/// A face that has been projected onto the page.
+#[derive(Debug, Clone)]
+pub struct ProjectedFace {
+    /// The index of the face in the faces that were projected.
+    pub index: usize,
+    pub points: Vec<Point>,
+    /// The depth of the face's centroid.
+    pub depth: f64,
+}
+
+/// Projects world coordinates onto the page, centered on the origin. One unit
+/// in the world is one unit on the page for lengths that are parallel to the
+/// page, and, in perspective, at the distance of the origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Projection {
+    view: Mat3,
+    distance: Option<f64>,
+}
+
+impl Projection {
+    /// A projection through `view`, a rotation such as `Mat3::view` that
+    /// turns the world so that the viewer looks down -z with y up.
+    pub fn new(view: Mat3) -> Self {
+        Self {
+            view,
+            distance: None,
+        }
+    }
+
+    /// Looks straight down the -z axis, so that x and y are unchanged apart
+    /// from y being flipped to point down the page.
+    pub fn orthographic() -> Self {
+        Self::new(Mat3::identity())
+    }
+
+    pub fn isometric() -> Self {
+        Self::new(Mat3::isometric())
+    }
+
+    pub fn dimetric(elevation: f64) -> Self {
+        Self::new(Mat3::dimetric(elevation))
+    }
+
+    /// Looks down the -z axis from a camera on the +z axis at `distance`.
+    pub fn perspective(distance: f64) -> Self {
+        Self::orthographic().with_perspective(distance)
+    }
+
+    /// This projection with a camera at `distance` from the origin, which
+    /// makes farther things smaller.
+    pub fn with_perspective(self, distance: f64) -> Self {
+        Self {
+            distance: Some(distance),
+            ..self
+        }
+    }
+
+    /// The direction from `p` toward the viewer, in world coordinates.
+    fn toward_viewer(&self, p: &Vec3) -> Vec3 {
+        let back = self.view.row(2);
+        match self.distance {
+            Some(d) => back * d - *p,
+            None => back,
+        }
+    }
+
+    /// How far `p` is from the viewer along the direction they look in, where
+    /// larger depths are farther away. For orthographic projections this is
+    /// measured from the plane through the origin.
+    pub fn depth(&self, p: &Vec3) -> f64 {
+        let z = self.view.row(2).dot(p);
+        match self.distance {
+            Some(d) => d - z,
+            None => -z,
+        }
+    }
+
+    /// The point on the page that `p` lands on. Points behind a perspective
+    /// camera have no meaningful projection.
+    pub fn project(&self, p: &Vec3) -> Point {
+        let v = self.view * *p;
+        let k = match self.distance {
+            Some(d) => d / (d - v.z),
+            None => 1.0,
+        };
+        Point::new(v.x * k, -v.y * k)
+    }
+
+    /// Whether the viewer sees the front of `face`.
+    pub fn faces_viewer(&self, face: &Face) -> bool {
+        face.normal().dot(&self.toward_viewer(&face.centroid())) > 0.0
+    }
+
+    /// Projects the faces that the viewer sees the front of, sorted from the
+    /// farthest to the nearest so that painting them in order leaves the
+    /// nearer faces on top. Sorting by the centroids is only an approximation,
+    /// which can fail for long faces that overlap in depth.
+    pub fn project_faces(&self, faces: &[Face]) -> Vec<ProjectedFace> {
+        let mut projected = faces
+            .iter()
+            .enumerate()
+            .filter(|(_, face)| self.faces_viewer(face))
+            .map(|(index, face)| ProjectedFace {
+                index,
+                points: face.vertices.iter().map(|v| self.project(v)).collect(),
+                depth: self.depth(&face.centroid()),
+            })
+            .collect::<Vec<_>>();
+        projected.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+        projected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    const EPS: f64 = 1e-9;
+
+    fn close(a: &Vec3, b: &Vec3) -> bool {
+        a.distance(b) < EPS
+    }
+
+    fn close_pt(a: &Point, b: &Point) -> bool {
+        a.distance(b) < EPS
+    }
+
+    // A cube from -0.5 to 0.5, with its faces in the order +x, -x, +y, -y, +z
+    // and -z, each counterclockwise from outside.
+    fn cube() -> Vec<Face> {
+        let axes = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        ];
+        axes.iter()
+            .enumerate()
+            .flat_map(|(i, &a)| [a, -a].map(|n| (n, axes[(i + 1) % 3])))
+            .map(|(n, u)| {
+                let v = n.cross(&u);
+                let c = n * 0.5;
+                Face::new(vec![
+                    c - u * 0.5 - v * 0.5,
+                    c + u * 0.5 - v * 0.5,
+                    c + u * 0.5 + v * 0.5,
+                    c - u * 0.5 + v * 0.5,
+                ])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn quat_matches_mat3() {
+        let v = Vec3::new(0.3, -1.2, 2.5);
+        let axes = [
+            (
+                Vec3::new(1.0, 0.0, 0.0),
+                Mat3::rotation_x as fn(f64) -> Mat3,
+            ),
+            (Vec3::new(0.0, 1.0, 0.0), Mat3::rotation_y),
+            (Vec3::new(0.0, 0.0, 1.0), Mat3::rotation_z),
+        ];
+        for angle in [0.0, 0.4, FRAC_PI_2, 2.5, -1.1] {
+            for (axis, rotation) in axes.iter() {
+                let q = Quat::from_axis_angle(axis, angle);
+                assert!(close(&q.rotate(&v), &(rotation(angle) * v)));
+                assert!(close(&(q.to_mat3() * v), &(rotation(angle) * v)));
+            }
+        }
+
+        let axis = Vec3::new(1.0, 2.0, -0.5);
+        let (a, b) = (
+            Quat::from_axis_angle(&axis, 0.7),
+            Quat::from_axis_angle(&Vec3::new(0.0, 1.0, 1.0), -1.3),
+        );
+        assert!(close(&(a * b).rotate(&v), &a.rotate(&b.rotate(&v))));
+        assert!(close(
+            &((a * b).to_mat3() * v),
+            &(a.to_mat3() * b.to_mat3() * v)
+        ));
+        assert!(close(&(Mat3::rotation(&axis, 0.7) * v), &a.rotate(&v)));
+    }
+
+    #[test]
+    fn slerp_endpoints() {
+        let v = Vec3::new(1.0, 0.5, -0.25);
+        let a = Quat::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), 0.3);
+        // more than half a turn away, so slerp takes the other way around.
+        let b = Quat::from_axis_angle(&Vec3::new(1.0, 1.0, 0.0), 4.0);
+        assert!(close(&a.slerp(&b, 0.0).rotate(&v), &a.rotate(&v)));
+        assert!(close(&a.slerp(&b, 1.0).rotate(&v), &b.rotate(&v)));
+
+        let z = Vec3::new(0.0, 0.0, 1.0);
+        let mid = Quat::identity().slerp(&Quat::from_axis_angle(&z, FRAC_PI_2), 0.5);
+        assert!(close(
+            &mid.rotate(&v),
+            &Quat::from_axis_angle(&z, FRAC_PI_4).rotate(&v)
+        ));
+    }
+
+    #[test]
+    fn look_at() {
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let m = Mat4::look_at(&Vec3::new(0.0, 0.0, 5.0), &Vec3::zero(), &up);
+        assert!(close(
+            &m.transform_point(&Vec3::zero()),
+            &Vec3::new(0.0, 0.0, -5.0)
+        ));
+        assert!(close(
+            &m.transform_point(&Vec3::new(1.0, 2.0, 0.0)),
+            &Vec3::new(1.0, 2.0, -5.0)
+        ));
+
+        // from +x, looking back at the origin, -z is on the right.
+        let m = Mat4::look_at(&Vec3::new(5.0, 0.0, 0.0), &Vec3::zero(), &up);
+        assert!(close(
+            &m.transform_point(&Vec3::new(0.0, 1.0, -1.0)),
+            &Vec3::new(1.0, 1.0, -5.0)
+        ));
+    }
+
+    #[test]
+    fn orthographic() {
+        let m = Mat4::orthographic(-2.0, 2.0, -1.0, 1.0, 1.0, 11.0);
+        assert!(close(
+            &m.transform_point(&Vec3::new(2.0, 1.0, -1.0)),
+            &Vec3::new(1.0, 1.0, -1.0)
+        ));
+        assert!(close(
+            &m.transform_point(&Vec3::new(-2.0, -1.0, -11.0)),
+            &Vec3::new(-1.0, -1.0, 1.0)
+        ));
+        assert!(close(
+            &m.transform_point(&Vec3::new(0.0, 0.0, -6.0)),
+            &Vec3::zero()
+        ));
+    }
+
+    #[test]
+    fn perspective() {
+        let m = Mat4::perspective(FRAC_PI_2, 2.0, 1.0, 10.0);
+        // the corners of the near and far planes map to the corners of the cube.
+        assert!(close(
+            &m.transform_point(&Vec3::new(2.0, 1.0, -1.0)),
+            &Vec3::new(1.0, 1.0, -1.0)
+        ));
+        assert!(close(
+            &m.transform_point(&Vec3::new(-20.0, -10.0, -10.0)),
+            &Vec3::new(-1.0, -1.0, 1.0)
+        ));
+        // things twice as far away are half the size.
+        let (a, b) = (
+            m.transform_point(&Vec3::new(1.0, 1.0, -2.0)),
+            m.transform_point(&Vec3::new(1.0, 1.0, -4.0)),
+        );
+        assert!((a.x - 2.0 * b.x).abs() < EPS && (a.y - 2.0 * b.y).abs() < EPS);
+    }
+
+    #[test]
+    fn isometric_axes() {
+        let p = Projection::isometric();
+        let axes = [
+            p.project(&Vec3::new(1.0, 0.0, 0.0)),
+            p.project(&Vec3::new(0.0, 1.0, 0.0)),
+            p.project(&Vec3::new(0.0, 0.0, 1.0)),
+        ];
+        let len = (2.0_f64 / 3.0).sqrt();
+        for a in axes.iter() {
+            assert!((a.length() - len).abs() < EPS);
+        }
+        assert!(close_pt(&axes[1], &Point::new(0.0, -len)));
+        // x goes down to the right and z down to the left, 120° from y.
+        assert!(axes[0].x() > 0.0 && axes[2].x() < 0.0);
+        for (a, b) in [(0, 1), (1, 2), (2, 0)] {
+            let cos = axes[a].dot(&axes[b]) / (len * len);
+            assert!((cos - (2.0 * PI / 3.0).cos()).abs() < EPS);
+        }
+        assert!(close_pt(
+            &p.project(&Vec3::new(1.0, 1.0, 1.0)),
+            &Point::zero()
+        ));
+    }
+
+    #[test]
+    fn faces_viewer() {
+        let faces = cube();
+        let seen = |p: Projection| {
+            (0..faces.len())
+                .filter(|&i| p.faces_viewer(&faces[i]))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(seen(Projection::orthographic()), [4]);
+        assert_eq!(seen(Projection::isometric()), [0, 2, 4]);
+        assert_eq!(seen(Projection::perspective(5.0)), [4]);
+        // close up and off to the side, the camera sees the +x face too.
+        let p = Projection::new(Mat3::view(0.3, 0.0)).with_perspective(2.0);
+        assert_eq!(seen(p), [0, 4]);
+    }
+
+    #[test]
+    fn depth_order() {
+        let square = |z: f64| {
+            Face::new(vec![
+                Vec3::new(0.0, 0.0, z),
+                Vec3::new(1.0, 0.0, z),
+                Vec3::new(1.0, 1.0, z),
+                Vec3::new(0.0, 1.0, z),
+            ])
+        };
+        let faces = [square(0.0), square(-1.0), square(2.0), square(1.0)];
+        for p in [Projection::orthographic(), Projection::perspective(10.0)] {
+            let order = p
+                .project_faces(&faces)
+                .iter()
+                .map(|f| f.index)
+                .collect::<Vec<_>>();
+            assert_eq!(order, [1, 0, 3, 2]);
+        }
+
+        let p = Projection::new(Mat3::view(0.4, 0.3));
+        let projected = p.project_faces(&cube());
+        assert_eq!(projected.len(), 3);
+        assert!(projected.windows(2).all(|w| w[0].depth >= w[1].depth));
+    }
+}
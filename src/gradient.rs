@@ -0,0 +1,334 @@
+use crate::{color::LinearRgb, error::SketchError, Color};
+use cairo::{Context, LinearGradient, Mesh, MeshCorner, Pattern, RadialGradient};
+use std::f64::consts::PI;
+
+// The number of cairo stops a segment between two stops is split into when
+// the interpolation isn't sRGB, which is the only one cairo does itself.
+const SEGMENT_STEPS: usize = 16;
+
+// The number of wedges a conic gradient is drawn with.
+const CONIC_WEDGES: usize = 256;
+
+/// The color space that a gradient blends its stops in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// Blends the gamma encoded channels, as cairo and most tools do.
+    #[default]
+    Srgb,
+    /// Blends light intensity, which keeps blends of saturated colors bright.
+    LinearRgb,
+    /// Blends in OKLab, which changes perceived lightness evenly.
+    Oklab,
+}
+
+impl Interpolation {
+    /// The color `t` of the way from `a` to `b`.
+    pub fn mix(&self, a: &Color, b: &Color, t: f64) -> Color {
+        let lerp = |p: f64, q: f64| p + (q - p) * t;
+        let alpha = lerp(a.a as f64, b.a as f64).round().clamp(0.0, 255.0) as u8;
+        let c = match self {
+            Interpolation::Srgb => Color::from_rgb(
+                lerp(a.r as f64, b.r as f64).round() as u8,
+                lerp(a.g as f64, b.g as f64).round() as u8,
+                lerp(a.b as f64, b.b as f64).round() as u8,
+            ),
+            Interpolation::LinearRgb => {
+                let (p, q) = (a.to_linear(), b.to_linear());
+                Color::from_linear(LinearRgb {
+                    r: lerp(p.r, q.r),
+                    g: lerp(p.g, q.g),
+                    b: lerp(p.b, q.b),
+                })
+            }
+            Interpolation::Oklab => a.mix(b, t),
+        };
+        Color { a: alpha, ..c }
+    }
+}
+
+/// Where the offsets of a gradient's stops lie in user space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    /// Offsets run along the line from `(x0, y0)` to `(x1, y1)`.
+    Linear { x0: f64, y0: f64, x1: f64, y1: f64 },
+    /// Offsets run out from the circle of radius `r0` to the circle of radius
+    /// `r1` around `(cx, cy)`.
+    Radial { cx: f64, cy: f64, r0: f64, r1: f64 },
+    /// Offsets run clockwise around `(cx, cy)` starting at `angle`, in
+    /// radians, out to a radius of `r`.
+    Conic {
+        cx: f64,
+        cy: f64,
+        r: f64,
+        angle: f64,
+    },
+}
+
+/// A gradient with any number of color stops, which can be used as a cairo
+/// pattern or sampled for the colors of individual shapes.
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    shape: Shape,
+    stops: Vec<(f64, Color)>,
+    interpolation: Interpolation,
+}
+
+impl Gradient {
+    pub fn new(shape: Shape) -> Self {
+        Self {
+            shape,
+            stops: Vec::new(),
+            interpolation: Interpolation::default(),
+        }
+    }
+
+    pub fn linear(x0: f64, y0: f64, x1: f64, y1: f64) -> Self {
+        Self::new(Shape::Linear { x0, y0, x1, y1 })
+    }
+
+    pub fn radial(cx: f64, cy: f64, r0: f64, r1: f64) -> Self {
+        Self::new(Shape::Radial { cx, cy, r0, r1 })
+    }
+
+    pub fn conic(cx: f64, cy: f64, r: f64, angle: f64) -> Self {
+        Self::new(Shape::Conic { cx, cy, r, angle })
+    }
+
+    /// Adds a stop at `offset`, from 0 to 1. A stop at the same offset as an
+    /// earlier one goes after it, which makes a hard edge between them.
+    pub fn with_stop(mut self, offset: f64, color: Color) -> Self {
+        let offset = offset.clamp(0.0, 1.0);
+        let ix = self.stops.partition_point(|(o, _)| *o <= offset);
+        self.stops.insert(ix, (offset, color));
+        self
+    }
+
+    /// Adds evenly spaced stops for `colors`, such as a theme, so that the
+    /// gradient is a ramp through them.
+    pub fn with_colors(self, colors: &[Color]) -> Self {
+        let n = colors.len().max(2) - 1;
+        colors
+            .iter()
+            .enumerate()
+            .fold(self, |g, (i, c)| g.with_stop(i as f64 / n as f64, *c))
+    }
+
+    pub fn with_interpolation(self, interpolation: Interpolation) -> Self {
+        Self {
+            interpolation,
+            ..self
+        }
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    pub fn stops(&self) -> &[(f64, Color)] {
+        &self.stops
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// The color at offset `t`, which is clamped to the range of the stops. A
+    /// gradient with no stops is transparent.
+    pub fn sample(&self, t: f64) -> Color {
+        let ix = self.stops.partition_point(|(o, _)| *o <= t);
+        match (ix, self.stops.len()) {
+            (_, 0) => Color::from_rgba(0, 0, 0, 0.0),
+            (0, _) => self.stops[0].1,
+            (ix, n) if ix == n => self.stops[n - 1].1,
+            (ix, _) => {
+                let (o0, c0) = self.stops[ix - 1];
+                let (o1, c1) = self.stops[ix];
+                self.interpolation.mix(&c0, &c1, (t - o0) / (o1 - o0))
+            }
+        }
+    }
+
+    // The stops to give cairo, which blends them in sRGB, so the segments of
+    // other interpolations are approximated by many short sRGB ones.
+    fn cairo_stops(&self) -> Vec<(f64, Color)> {
+        if self.interpolation == Interpolation::Srgb {
+            return self.stops.clone();
+        }
+        let mut stops = Vec::new();
+        for pair in self.stops.windows(2) {
+            let ((o0, c0), (o1, c1)) = (pair[0], pair[1]);
+            stops.push((o0, c0));
+            for i in 1..SEGMENT_STEPS {
+                let t = i as f64 / SEGMENT_STEPS as f64;
+                stops.push((o0 + (o1 - o0) * t, self.interpolation.mix(&c0, &c1, t)));
+            }
+        }
+        stops.extend(self.stops.last().copied());
+        stops
+    }
+
+    /// A cairo pattern that paints the gradient.
+    pub fn pattern(&self) -> Result<Pattern, SketchError> {
+        let pattern = match self.shape {
+            Shape::Linear { x0, y0, x1, y1 } => {
+                let g = LinearGradient::new(x0, y0, x1, y1);
+                for (o, c) in self.cairo_stops() {
+                    g.add_color_stop_rgba(o, c.r_f64(), c.g_f64(), c.b_f64(), alpha(&c));
+                }
+                Pattern::clone(&g)
+            }
+            Shape::Radial { cx, cy, r0, r1 } => {
+                let g = RadialGradient::new(cx, cy, r0, cx, cy, r1);
+                for (o, c) in self.cairo_stops() {
+                    g.add_color_stop_rgba(o, c.r_f64(), c.g_f64(), c.b_f64(), alpha(&c));
+                }
+                Pattern::clone(&g)
+            }
+            Shape::Conic { cx, cy, r, angle } => Pattern::clone(&self.conic_mesh(cx, cy, r, angle)),
+        };
+        pattern.status()?;
+        Ok(pattern)
+    }
+
+    // cairo has no conic gradients, so the circle is split into thin wedges,
+    // each a mesh patch blending the colors at its two edges.
+    fn conic_mesh(&self, cx: f64, cy: f64, r: f64, angle: f64) -> Mesh {
+        let mesh = Mesh::new();
+        // the wedges are triangles, so they reach a little further than r for
+        // their chords to cover the circle.
+        let r = r / (PI / CONIC_WEDGES as f64).cos();
+        let set = |corner, c: &Color| {
+            mesh.set_corner_color_rgba(corner, c.r_f64(), c.g_f64(), c.b_f64(), alpha(c))
+        };
+        for i in 0..CONIC_WEDGES {
+            let (t0, t1) = (
+                i as f64 / CONIC_WEDGES as f64,
+                (i + 1) as f64 / CONIC_WEDGES as f64,
+            );
+            let (a0, a1) = (angle + t0 * 2.0 * PI, angle + t1 * 2.0 * PI);
+            let (c0, c1) = (self.sample(t0), self.sample(t1));
+            mesh.begin_patch();
+            mesh.move_to(cx, cy);
+            mesh.line_to(cx + r * a0.cos(), cy + r * a0.sin());
+            mesh.line_to(cx + r * a1.cos(), cy + r * a1.sin());
+            mesh.line_to(cx, cy);
+            set(MeshCorner::MeshCorner0, &c0);
+            set(MeshCorner::MeshCorner1, &c0);
+            set(MeshCorner::MeshCorner2, &c1);
+            set(MeshCorner::MeshCorner3, &c1);
+            mesh.end_patch();
+        }
+        mesh
+    }
+
+    /// Makes the gradient the source of `ctx`.
+    pub fn set(&self, ctx: &Context) -> Result<(), SketchError> {
+        ctx.set_source(self.pattern()?)?;
+        Ok(())
+    }
+}
+
+fn alpha(c: &Color) -> f64 {
+    c.a as f64 / 255.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(c: Color) -> u32 {
+        c.to_rgb_u32()
+    }
+
+    fn ramp(interpolation: Interpolation) -> Gradient {
+        Gradient::linear(0.0, 0.0, 100.0, 0.0)
+            .with_stop(0.0, Color::black())
+            .with_stop(1.0, Color::white())
+            .with_interpolation(interpolation)
+    }
+
+    #[test]
+    fn sample_at_stops() {
+        let g = Gradient::linear(0.0, 0.0, 1.0, 0.0).with_colors(&[
+            Color::from_rgb_u32(0xff0000),
+            Color::from_rgb_u32(0x00ff00),
+            Color::from_rgb_u32(0x0000ff),
+        ]);
+        assert_eq!(
+            g.stops().iter().map(|(o, _)| *o).collect::<Vec<_>>(),
+            vec![0.0, 0.5, 1.0]
+        );
+        assert_eq!(rgb(g.sample(0.0)), 0xff0000);
+        assert_eq!(rgb(g.sample(0.5)), 0x00ff00);
+        assert_eq!(rgb(g.sample(1.0)), 0x0000ff);
+        assert_eq!(rgb(g.sample(0.25)), 0x808000);
+        assert_eq!(rgb(g.sample(0.75)), 0x008080);
+        // offsets past the ends take the end colors.
+        assert_eq!(rgb(g.sample(-1.0)), 0xff0000);
+        assert_eq!(rgb(g.sample(2.0)), 0x0000ff);
+    }
+
+    #[test]
+    fn stops_are_ordered() {
+        let g = Gradient::linear(0.0, 0.0, 1.0, 0.0)
+            .with_stop(1.0, Color::white())
+            .with_stop(0.5, Color::from_rgb_u32(0xff0000))
+            .with_stop(0.5, Color::from_rgb_u32(0x0000ff))
+            .with_stop(-3.0, Color::black());
+        assert_eq!(
+            g.stops().iter().map(|(o, _)| *o).collect::<Vec<_>>(),
+            vec![0.0, 0.5, 0.5, 1.0]
+        );
+        // the second of two stops at one offset wins from there on.
+        assert_eq!(rgb(g.sample(0.5)), 0x0000ff);
+        assert_eq!(rgb(g.sample(0.4999)), 0xff0000);
+    }
+
+    #[test]
+    fn empty() {
+        let g = Gradient::radial(0.0, 0.0, 0.0, 1.0);
+        assert_eq!(g.sample(0.5).a, 0);
+        assert!(g.pattern().is_ok());
+    }
+
+    #[test]
+    fn interpolation_midpoints() {
+        assert_eq!(rgb(ramp(Interpolation::Srgb).sample(0.5)), 0x808080);
+        // half the light of white is much lighter than the half way code.
+        assert_eq!(rgb(ramp(Interpolation::LinearRgb).sample(0.5)), 0xbcbcbc);
+        // and OKLab's half way lightness is darker.
+        assert_eq!(rgb(ramp(Interpolation::Oklab).sample(0.5)), 0x636363);
+
+        let (red, blue) = (Color::from_rgb_u32(0xff0000), Color::from_rgb_u32(0x0000ff));
+        assert_eq!(rgb(Interpolation::Srgb.mix(&red, &blue, 0.5)), 0x800080);
+        assert_eq!(
+            rgb(Interpolation::Oklab.mix(&red, &blue, 0.5)),
+            rgb(red.mix(&blue, 0.5))
+        );
+        let clear = Color::from_rgba(0, 0, 0xff, 0.0);
+        for i in [
+            Interpolation::Srgb,
+            Interpolation::LinearRgb,
+            Interpolation::Oklab,
+        ] {
+            assert_eq!(i.mix(&red, &clear, 0.5).a, 128, "{:?}", i);
+            assert_eq!(rgb(i.mix(&red, &blue, 0.0)), 0xff0000, "{:?}", i);
+            assert_eq!(rgb(i.mix(&red, &blue, 1.0)), 0x0000ff, "{:?}", i);
+        }
+    }
+
+    #[test]
+    fn cairo_stops() {
+        assert_eq!(ramp(Interpolation::Srgb).cairo_stops().len(), 2);
+        let stops = ramp(Interpolation::Oklab).cairo_stops();
+        assert_eq!(stops.len(), SEGMENT_STEPS + 1);
+        assert!(stops.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(rgb(stops[SEGMENT_STEPS / 2].1), 0x636363);
+        for g in [
+            ramp(Interpolation::Oklab),
+            Gradient::conic(5.0, 5.0, 5.0, 0.0).with_colors(&[Color::black(), Color::white()]),
+        ] {
+            assert!(g.pattern().is_ok());
+        }
+    }
+}
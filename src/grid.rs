@@ -0,0 +1,390 @@
+use crate::{error::SketchError, Point, Rect};
+use cairo::Context;
+use rand::{Rng, RngCore};
+
+/// How the cells of a `Grid` are arranged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lattice {
+    /// Rectangular cells in rows and columns.
+    #[default]
+    Square,
+    /// Pointy topped hexagons, with odd rows shifted right by half a cell.
+    Hex,
+    /// Triangles that alternate between pointing up and down along each row,
+    /// starting with one that points up.
+    Triangular,
+}
+
+/// A layout of `nx` columns and `ny` rows of cells stretched over a rect.
+/// Cells are indexed by column `i` and row `j`, and the point of a cell is its
+/// center.
+#[derive(Debug, Clone, Copy)]
+pub struct Grid {
+    frame: Rect,
+    bounds: Rect,
+    nx: usize,
+    ny: usize,
+    lattice: Lattice,
+}
+
+impl Grid {
+    /// A square grid that fills `frame`.
+    pub fn new(frame: Rect, nx: usize, ny: usize) -> Self {
+        Self {
+            frame,
+            bounds: frame,
+            nx,
+            ny,
+            lattice: Lattice::Square,
+        }
+    }
+
+    pub fn hex(frame: Rect, nx: usize, ny: usize) -> Self {
+        Self::new(frame, nx, ny).with_lattice(Lattice::Hex)
+    }
+
+    pub fn triangular(frame: Rect, nx: usize, ny: usize) -> Self {
+        Self::new(frame, nx, ny).with_lattice(Lattice::Triangular)
+    }
+
+    pub fn with_lattice(self, lattice: Lattice) -> Self {
+        Self { lattice, ..self }
+    }
+
+    /// Leaves a margin of `m` between the frame and the cells.
+    pub fn with_margin(self, m: f64) -> Self {
+        self.with_margins(m, m)
+    }
+
+    /// Leaves a margin of `mx` at the left and right and `my` at the top and
+    /// bottom.
+    pub fn with_margins(self, mx: f64, my: f64) -> Self {
+        Self {
+            bounds: self.frame.inset_xy(mx, my),
+            ..self
+        }
+    }
+
+    /// Leaves a margin of `k` cells around the cells, so that a square grid
+    /// with a margin of half a cell has its points spaced a whole cell from
+    /// the edges of the frame.
+    pub fn with_cell_margin(self, k: f64) -> Self {
+        let (ex, ey) = self.extent();
+        let mx = k * self.frame.width() / (ex + 2.0 * k);
+        let my = k * self.frame.height() / (ey + 2.0 * k);
+        self.with_margins(mx, my)
+    }
+
+    pub fn nx(&self) -> usize {
+        self.nx
+    }
+
+    pub fn ny(&self) -> usize {
+        self.ny
+    }
+
+    pub fn lattice(&self) -> Lattice {
+        self.lattice
+    }
+
+    /// The rect the grid was laid out over, including the margins.
+    pub fn frame(&self) -> &Rect {
+        &self.frame
+    }
+
+    /// The rect that holds the cells.
+    pub fn bounds(&self) -> &Rect {
+        &self.bounds
+    }
+
+    // The width and height of the cells together, measured in cells.
+    fn extent(&self) -> (f64, f64) {
+        let (nx, ny) = (self.nx as f64, self.ny as f64);
+        match self.lattice {
+            Lattice::Square => (nx, ny),
+            Lattice::Hex => (nx + 0.5, 0.75 * ny + 0.25),
+            Lattice::Triangular => ((nx + 1.0) / 2.0, ny),
+        }
+    }
+
+    /// The width of a cell.
+    pub fn dx(&self) -> f64 {
+        self.bounds.width() / self.extent().0
+    }
+
+    /// The height of a cell. Hex rows overlap, so they are less than this
+    /// apart.
+    pub fn dy(&self) -> f64 {
+        self.bounds.height() / self.extent().1
+    }
+
+    /// The bounding rect of the cell at `(i, j)`.
+    pub fn cell(&self, i: usize, j: usize) -> Rect {
+        let (dx, dy) = (self.dx(), self.dy());
+        let (l, t) = (self.bounds.left(), self.bounds.top());
+        match self.lattice {
+            Lattice::Square => Rect::from_xywh(l + dx * i as f64, t + dy * j as f64, dx, dy),
+            Lattice::Hex => {
+                let shift = if j % 2 == 1 { 0.5 } else { 0.0 };
+                Rect::from_xywh(
+                    l + dx * (i as f64 + shift),
+                    t + 0.75 * dy * j as f64,
+                    dx,
+                    dy,
+                )
+            }
+            Lattice::Triangular => {
+                Rect::from_xywh(l + dx * i as f64 / 2.0, t + dy * j as f64, dx, dy)
+            }
+        }
+    }
+
+    /// The center of the cell at `(i, j)`. For triangles this is the centroid,
+    /// which sits a third of the way up from the base.
+    pub fn point(&self, i: usize, j: usize) -> Point {
+        let cell = self.cell(i, j);
+        match self.lattice {
+            Lattice::Triangular => {
+                let k = if self.points_up(i, j) { 2.0 } else { 1.0 };
+                Point::new(cell.center().x(), cell.top() + cell.height() * k / 3.0)
+            }
+            _ => cell.center(),
+        }
+    }
+
+    /// The point of the cell at `(i, j)` moved by a random amount, up to
+    /// `amount` of the way to the edge of its bounding rect on each axis.
+    pub fn jittered(&self, rng: &mut dyn RngCore, i: usize, j: usize, amount: f64) -> Point {
+        let p = self.point(i, j);
+        let (rx, ry) = (amount * self.dx() / 2.0, amount * self.dy() / 2.0);
+        // an empty range can't be sampled, as when a cell has no width.
+        if rx <= 0.0 || ry <= 0.0 {
+            return p;
+        }
+        p + Point::new(rng.gen_range(-rx..rx), rng.gen_range(-ry..ry))
+    }
+
+    #[allow(clippy::manual_is_multiple_of)]
+    fn points_up(&self, i: usize, j: usize) -> bool {
+        (i + j) % 2 == 0
+    }
+
+    /// The corners of the cell at `(i, j)`, clockwise.
+    pub fn outline(&self, i: usize, j: usize) -> Vec<Point> {
+        let r = self.cell(i, j);
+        let c = r.center();
+        match self.lattice {
+            Lattice::Square => vec![
+                *r.top_left(),
+                r.top_right(),
+                *r.bottom_right(),
+                r.bottom_left(),
+            ],
+            Lattice::Hex => {
+                let (t, b) = (r.top() + r.height() / 4.0, r.bottom() - r.height() / 4.0);
+                vec![
+                    Point::new(c.x(), r.top()),
+                    Point::new(r.right(), t),
+                    Point::new(r.right(), b),
+                    Point::new(c.x(), r.bottom()),
+                    Point::new(r.left(), b),
+                    Point::new(r.left(), t),
+                ]
+            }
+            Lattice::Triangular if self.points_up(i, j) => vec![
+                Point::new(c.x(), r.top()),
+                *r.bottom_right(),
+                r.bottom_left(),
+            ],
+            Lattice::Triangular => {
+                vec![*r.top_left(), r.top_right(), Point::new(c.x(), r.bottom())]
+            }
+        }
+    }
+
+    /// The indices of the cells, row by row.
+    pub fn indices(&self) -> impl Iterator<Item = (usize, usize)> {
+        let (nx, ny) = (self.nx, self.ny);
+        (0..ny).flat_map(move |j| (0..nx).map(move |i| (i, j)))
+    }
+
+    /// The points of the cells, row by row.
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.indices().map(|(i, j)| self.point(i, j))
+    }
+
+    /// The bounding rects of the cells, row by row.
+    pub fn cells(&self) -> impl Iterator<Item = Rect> + '_ {
+        self.indices().map(|(i, j)| self.cell(i, j))
+    }
+
+    /// The indices of the cell that holds `p`, or `None` if it's outside of
+    /// every cell.
+    pub fn cell_at(&self, p: &Point) -> Option<(usize, usize)> {
+        if self.nx == 0 || self.ny == 0 {
+            return None;
+        }
+        let (dx, dy) = (self.dx(), self.dy());
+        let rel = *p - *self.bounds.top_left();
+        // a guess that is at most one cell off in each direction, since cells
+        // of the other lattices overlap their neighbors' bounding rects.
+        let (gi, gj) = match self.lattice {
+            Lattice::Square => (rel.x() / dx, rel.y() / dy),
+            Lattice::Hex => (rel.x() / dx, rel.y() / (0.75 * dy)),
+            Lattice::Triangular => (2.0 * rel.x() / dx, rel.y() / dy),
+        };
+        let near = |g: f64, n: usize| (-1.0..=n as f64 + 1.0).contains(&g);
+        if !near(gi, self.nx) || !near(gj, self.ny) {
+            return None;
+        }
+        let (gi, gj) = (gi.floor() as i64, gj.floor() as i64);
+        (gj - 1..=gj + 1)
+            .flat_map(|j| (gi - 1..=gi + 1).map(move |i| (i, j)))
+            .filter(|&(i, j)| i >= 0 && j >= 0 && (i as usize) < self.nx && (j as usize) < self.ny)
+            .map(|(i, j)| (i as usize, j as usize))
+            .find(|&(i, j)| contains(&self.outline(i, j), p))
+    }
+
+    /// Strokes a dashed overlay of the grid in the current source, for
+    /// debugging. Square grids get lines through the rows and columns of
+    /// points that run across the frame, and the others get cell outlines.
+    pub fn render(&self, ctx: &Context) -> Result<(), SketchError> {
+        ctx.save()?;
+        ctx.new_path();
+        match self.lattice {
+            Lattice::Square => {
+                for i in 0..self.nx {
+                    let x = self.point(i, 0).x();
+                    ctx.move_to(x, self.frame.top());
+                    ctx.line_to(x, self.frame.bottom());
+                }
+                for j in 0..self.ny {
+                    let y = self.point(0, j).y();
+                    ctx.move_to(self.frame.left(), y);
+                    ctx.line_to(self.frame.right(), y);
+                }
+            }
+            _ => {
+                for (i, j) in self.indices() {
+                    let pts = self.outline(i, j);
+                    ctx.move_to(pts[0].x(), pts[0].y());
+                    for p in &pts[1..] {
+                        ctx.line_to(p.x(), p.y());
+                    }
+                    ctx.close_path();
+                }
+            }
+        }
+        ctx.set_line_width(1.0);
+        ctx.set_dash(&[1.0, 4.0], 0.0);
+        ctx.stroke()?;
+        ctx.restore()?;
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Grid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.nx, self.ny)
+    }
+}
+
+// Whether `p` is inside or on the edge of the convex polygon `pts`, whose
+// corners run clockwise.
+fn contains(pts: &[Point], p: &Point) -> bool {
+    pts.iter()
+        .zip(pts.iter().cycle().skip(1))
+        .all(|(a, b)| (*b - *a).cross(&(*p - *a)) >= 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    #[test]
+    fn jittered_in_cell() {
+        let mut rng = Pcg64::seed_from_u64(1);
+        let grid = Grid::new(Rect::from_wh(100.0, 50.0), 4, 2);
+        for (i, j) in grid.indices() {
+            let (p, q) = (grid.point(i, j), grid.jittered(&mut rng, i, j, 1.0));
+            assert!((q.x() - p.x()).abs() <= grid.dx() / 2.0);
+            assert!((q.y() - p.y()).abs() <= grid.dy() / 2.0);
+        }
+    }
+
+    #[test]
+    fn jittered_without_room() {
+        let mut rng = Pcg64::seed_from_u64(1);
+        for frame in [Rect::from_wh(100.0, 0.0), Rect::from_wh(0.0, 50.0)] {
+            let grid = Grid::new(frame, 4, 2);
+            assert_eq!(grid.jittered(&mut rng, 1, 1, 0.5), grid.point(1, 1));
+        }
+        let grid = Grid::new(Rect::from_wh(100.0, 50.0), 4, 2);
+        assert_eq!(grid.jittered(&mut rng, 1, 1, 0.0), grid.point(1, 1));
+    }
+
+    fn lattices(frame: Rect) -> [Grid; 3] {
+        [
+            Grid::new(frame, 5, 4),
+            Grid::hex(frame, 5, 4),
+            Grid::triangular(frame, 5, 4),
+        ]
+    }
+
+    #[test]
+    fn cell_at_round_trips() {
+        let frame = Rect::from_xywh(10.0, 20.0, 300.0, 200.0);
+        for grid in lattices(frame) {
+            for grid in [grid, grid.with_margins(15.0, 5.0)] {
+                for (i, j) in grid.indices() {
+                    let p = grid.point(i, j);
+                    assert_eq!(grid.cell_at(&p), Some((i, j)), "{:?} {}", grid.lattice(), p);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cell_at_outside() {
+        let frame = Rect::from_xywh(10.0, 20.0, 300.0, 200.0);
+        for grid in lattices(frame) {
+            for p in [
+                Point::new(0.0, 0.0),
+                Point::new(400.0, 100.0),
+                Point::new(100.0, -50.0),
+                Point::new(-1e9, 1e9),
+            ] {
+                assert_eq!(grid.cell_at(&p), None, "{:?} {}", grid.lattice(), p);
+            }
+        }
+        // the corners of a hex grid fall between the cells.
+        let grid = Grid::hex(frame, 5, 4);
+        assert_eq!(grid.cell_at(&Point::new(10.5, 20.5)), None);
+        assert_eq!(Grid::new(frame, 0, 4).cell_at(&frame.center()), None);
+    }
+
+    #[test]
+    fn cell_margin() {
+        let frame = Rect::from_xywh(0.0, 0.0, 300.0, 200.0);
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        for grid in lattices(frame) {
+            let g = grid.with_cell_margin(1.5);
+            let (mx, my) = (g.bounds().left(), g.bounds().top());
+            assert!(close(mx, 1.5 * g.dx()), "{:?}", grid.lattice());
+            assert!(close(my, 1.5 * g.dy()), "{:?}", grid.lattice());
+            assert!(close(frame.right() - g.bounds().right(), mx));
+            assert!(close(frame.bottom() - g.bounds().bottom(), my));
+            assert_eq!(g.frame(), &frame);
+        }
+
+        // half a cell of margin spaces the points a whole cell from the edges.
+        let g = Grid::new(frame, 5, 3).with_cell_margin(0.5);
+        assert!(close(g.dx(), 50.0) && close(g.dy(), 50.0));
+        assert!(close(g.point(0, 0).x(), 50.0) && close(g.point(0, 0).y(), 50.0));
+        assert!(close(g.point(4, 2).x(), 250.0) && close(g.point(4, 2).y(), 150.0));
+        let g = Grid::new(frame, 5, 3).with_cell_margin(0.0);
+        assert_eq!(g.bounds(), &frame);
+    }
+}
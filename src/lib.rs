@@ -1,23 +1,49 @@
 use byteorder::{BigEndian, ByteOrder};
 use cairo::Context;
+use common::Seed;
 use core::fmt;
+use error::SketchError;
 use memmap::{Mmap, MmapOptions};
-use rand::{distributions::Uniform, prelude::Distribution};
+use rand::{distributions::Uniform, prelude::Distribution, SeedableRng};
 use rand_pcg::Pcg64;
-use std::{error::Error, fs, io, path::Path, str::FromStr};
-
+use std::{
+    error::Error,
+    fs, io,
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+#[allow(dead_code)]
 mod a;
 mod b;
 mod c;
+#[allow(dead_code, unused_variables)]
 mod d;
 mod e;
+mod f;
 
+pub mod cli;
+pub mod color;
 pub mod common;
+pub mod error;
+pub mod geom;
+pub mod geom3d;
+pub mod gradient;
+pub mod grid;
+pub mod metadata;
+pub mod noise;
+pub mod params;
+pub mod path;
+pub mod polygon;
+pub mod sampling;
+pub mod sketch;
+pub mod voronoi;
 
 const DARKER: f64 = 0.7;
 const BRIGHTER: f64 = 1.0 / DARKER;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Point {
     x: f64,
     y: f64,
@@ -43,7 +69,7 @@ impl std::fmt::Display for Point {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Rect {
     tl: Point,
     br: Point,
@@ -69,10 +95,7 @@ impl Rect {
     }
 
     pub fn new(tl: &Point, br: &Point) -> Self {
-        Self {
-            tl: tl.clone(),
-            br: br.clone(),
-        }
+        Self { tl: *tl, br: *br }
     }
 
     pub fn x(&self) -> f64 {
@@ -214,6 +237,11 @@ impl Color {
         )
     }
 
+    /// The color as `0x00rrggbb`, which is how colors are stored in themes.
+    pub fn to_rgb_u32(&self) -> u32 {
+        (self.r as u32) << 16 | (self.g as u32) << 8 | self.b as u32
+    }
+
     pub fn with_alpha(&self, a: f64) -> Self {
         Self::from_rgba(self.r, self.g, self.b, a)
     }
@@ -229,6 +257,8 @@ impl Color {
         }
     }
 
+    /// A rough brightness that skips linearizing the channels. Use
+    /// `relative_luminance` to compare colors by how light they look.
     pub fn luminance(&self) -> f64 {
         let r = self.r as f64 / 256.0;
         let g = self.g as f64 / 256.0;
@@ -272,6 +302,8 @@ impl Color {
         self.a as f64 * 255.0
     }
 
+    /// Scales the channels up, which clips and shifts the hue of bright colors.
+    /// `lighten` keeps the hue.
     pub fn brighter(&self, k: f64) -> Self {
         let (r, g, b) = self.as_f64();
         let k = BRIGHTER.powf(k);
@@ -283,6 +315,7 @@ impl Color {
         }
     }
 
+    /// Scales the channels down. `darken` is the perceptual version.
     pub fn darker(&self, k: f64) -> Self {
         let (r, g, b) = self.as_f64();
         let k = DARKER.powf(k);
@@ -303,46 +336,412 @@ impl Color {
     }
 }
 
+// The alpha is only written when the color isn't opaque, so that this can be
+// parsed back by `FromStr`.
 impl std::fmt::Display for Color {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)?;
+        if self.a != 0xff {
+            write!(f, "{:02x}", self.a)?;
+        }
+        Ok(())
+    }
+}
+
+/// The number of colors in each theme.
+pub const THEME_COLORS: usize = 5;
+
+const THEME_SIZE: usize = THEME_COLORS * 4;
+
+/// Whether a color is light or dark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    Light,
+    Dark,
+}
+
+impl Tone {
+    pub fn of(color: &Color) -> Self {
+        // this is the luminance at which a color contrasts equally with black
+        // and with white.
+        if color.relative_luminance() > 0.18 {
+            Tone::Light
+        } else {
+            Tone::Dark
+        }
     }
 }
 
-const THEME_SIZE: usize = 20;
+/// A condition that a theme has to meet to be picked. The first color of a
+/// theme is taken to be its background, since that is how sketches use it.
+#[derive(Debug, Clone, Copy)]
+pub enum ThemeFilter {
+    /// Every other color has at least this contrast ratio with the background.
+    MinContrast(f64),
+
+    /// The background has this tone.
+    Background(Tone),
+}
+
+impl ThemeFilter {
+    pub fn matches(&self, theme: &[Color]) -> bool {
+        let (bg, rest) = match theme.split_first() {
+            Some(v) => v,
+            None => return false,
+        };
+        match self {
+            ThemeFilter::MinContrast(min) => rest.iter().all(|c| c.contrast(bg) >= *min),
+            ThemeFilter::Background(tone) => Tone::of(bg) == *tone,
+        }
+    }
+}
+
+impl FromStr for ThemeFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid theme filter: {}, expected min-contrast=<ratio> or background=light|dark",
+                s
+            )
+        };
+        let (name, value) = s.split_once('=').ok_or_else(invalid)?;
+        match (name, value) {
+            ("min-contrast", v) => Ok(ThemeFilter::MinContrast(v.parse().map_err(|_| invalid())?)),
+            ("background", "light") => Ok(ThemeFilter::Background(Tone::Light)),
+            ("background", "dark") => Ok(ThemeFilter::Background(Tone::Dark)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl std::fmt::Display for ThemeFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeFilter::MinContrast(v) => write!(f, "min-contrast={}", v),
+            ThemeFilter::Background(Tone::Light) => write!(f, "background=light"),
+            ThemeFilter::Background(Tone::Dark) => write!(f, "background=dark"),
+        }
+    }
+}
+
+/// How `Themes::pick` chooses a theme. By default the theme is drawn from the
+/// render's RNG, but it can be pinned to an index, drawn from a seed of its
+/// own, drawn only from themes that match some filters, or replaced by colors
+/// that aren't in the themes file at all.
+#[derive(Debug, Clone, Default)]
+pub struct ThemeChoice {
+    index: Option<usize>,
+    seed: Option<Seed>,
+    filters: Vec<ThemeFilter>,
+    colors: Vec<Color>,
+}
+
+impl ThemeChoice {
+    pub fn new(index: Option<usize>, seed: Option<Seed>, filters: Vec<ThemeFilter>) -> Self {
+        Self {
+            index,
+            seed,
+            filters,
+            colors: Vec::new(),
+        }
+    }
+
+    /// Uses `colors` as the theme instead of one from the themes file. Up to
+    /// `THEME_COLORS` colors can be given, and the missing slots are derived
+    /// from the given ones by stepping their lightness away from the
+    /// background, which is the first color.
+    pub fn with_colors(self, colors: Vec<Color>) -> Result<Self, SketchError> {
+        if colors.is_empty() || colors.len() > THEME_COLORS {
+            return Err(SketchError::ThemeChoice(format!(
+                "expected 1 to {} colors, got {}",
+                THEME_COLORS,
+                colors.len()
+            )));
+        }
+        let n = colors.len();
+        let step = match Tone::of(&colors[0]) {
+            Tone::Dark => 0.15,
+            Tone::Light => -0.15,
+        };
+        let colors = (0..THEME_COLORS)
+            .map(|i| match i.checked_sub(n) {
+                None => colors[i],
+                Some(k) => colors[k % n].lighten(step * (k / n + 1) as f64),
+            })
+            .collect();
+        Ok(Self { colors, ..self })
+    }
+
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn seed(&self) -> Option<Seed> {
+        self.seed
+    }
+
+    pub fn filters(&self) -> &[ThemeFilter] {
+        &self.filters
+    }
 
+    /// The colors that replace the picked theme, which is empty unless they
+    /// were set with `with_colors`.
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+}
+
+// A ThemeChoice resolved against a set of themes.
 #[derive(Debug)]
+struct Chooser {
+    index: Option<usize>,
+    seed: Option<Seed>,
+    candidates: Vec<usize>,
+    colors: Option<Vec<Color>>,
+}
+
+impl Chooser {
+    // Maps the index drawn from the render's RNG, out of `len` themes, to the
+    // index of the theme to use.
+    fn choose(&self, ix: usize, len: usize) -> usize {
+        if let Some(index) = self.index {
+            return index;
+        }
+        let n = self.candidates.len();
+        match self.seed {
+            Some(seed) => {
+                let mut rng = Pcg64::seed_from_u64(seed.value());
+                self.candidates[Uniform::new(0, n).sample(&mut rng)]
+            }
+            // with no filters this is just ix.
+            None => self.candidates[ix * n / len],
+        }
+    }
+}
+
+// The bytes of a themes file, either mapped from disk or held in memory.
+#[derive(Debug)]
+enum ThemeData {
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl ThemeData {
+    fn bytes(&self) -> &[u8] {
+        match self {
+            ThemeData::Mapped(mem) => mem,
+            ThemeData::Owned(data) => data,
+        }
+    }
+}
+
+// Cloning a Themes is cheap since clones share the same data, which lets
+// concurrent renders use one copy of the file.
+#[derive(Debug, Clone)]
 pub struct Themes {
-    mem: Mmap,
+    data: Arc<ThemeData>,
+    chooser: Option<Arc<Chooser>>,
+    picked: Option<Arc<Mutex<Option<usize>>>>,
 }
 
 impl Themes {
-    pub fn open<P: AsRef<Path>>(src: P) -> io::Result<Self> {
-        let f = fs::File::open(src)?;
-        Ok(Themes {
-            mem: unsafe { MmapOptions::new().map(&f)? },
+    /// Maps the themes file at `src`, which must hold at least one theme.
+    pub fn open<P: AsRef<Path>>(src: P) -> Result<Self, SketchError> {
+        let err = |source| SketchError::Themes {
+            path: Some(src.as_ref().to_path_buf()),
+            source,
+        };
+        let f = fs::File::open(&src).map_err(err)?;
+        // check the length first since an empty file cannot be mapped.
+        Self::validate(f.metadata().map_err(err)?.len() as usize)
+            .map_err(|e| e.at(src.as_ref().to_path_buf()))?;
+        let mem = unsafe { MmapOptions::new().map(&f) }.map_err(err)?;
+        Ok(Self::new(ThemeData::Mapped(mem)))
+    }
+
+    /// Themes held in memory, in the same format as a themes file.
+    pub fn from_bytes<B: Into<Vec<u8>>>(data: B) -> Result<Self, SketchError> {
+        let data = data.into();
+        Self::validate(data.len())?;
+        Ok(Self::new(ThemeData::Owned(data)))
+    }
+
+    /// Themes made of lists of colors, each of which must have `THEME_COLORS`
+    /// colors.
+    pub fn from_colors<I>(themes: I) -> Result<Self, SketchError>
+    where
+        I: IntoIterator<Item = Vec<Color>>,
+    {
+        let mut data = Vec::new();
+        for (i, theme) in themes.into_iter().enumerate() {
+            if theme.len() != THEME_COLORS {
+                return Err(SketchError::Themes {
+                    path: None,
+                    source: io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "theme {} has {} colors rather than {}",
+                            i,
+                            theme.len(),
+                            THEME_COLORS
+                        ),
+                    ),
+                });
+            }
+            for c in theme {
+                data.extend_from_slice(&c.to_rgb_u32().to_be_bytes());
+            }
+        }
+        Self::from_bytes(data)
+    }
+
+    /// Reads all of `r` into memory as with `from_bytes`.
+    pub fn from_reader<R: io::Read>(mut r: R) -> Result<Self, SketchError> {
+        let mut data = Vec::new();
+        r.read_to_end(&mut data)
+            .map_err(|source| SketchError::Themes { path: None, source })?;
+        Self::from_bytes(data)
+    }
+
+    fn new(data: ThemeData) -> Self {
+        Themes {
+            data: Arc::new(data),
+            chooser: None,
+            picked: None,
+        }
+    }
+
+    // `usize::is_multiple_of` is only stable since 1.87, so clippy's suggestion
+    // would raise the oldest rust that builds the crate.
+    #[allow(clippy::manual_is_multiple_of)]
+    fn validate(len: usize) -> Result<(), SketchError> {
+        let reason = if len == 0 {
+            String::from("there are no themes")
+        } else if len % THEME_SIZE != 0 {
+            format!(
+                "length {} is not a multiple of the theme size {}",
+                len, THEME_SIZE
+            )
+        } else {
+            return Ok(());
+        };
+        Err(SketchError::Themes {
+            path: None,
+            source: io::Error::new(io::ErrorKind::InvalidData, reason),
         })
     }
 
-    pub fn get(&self, idx: usize) -> Vec<Color> {
-        let off = idx * THEME_SIZE;
-        let mut colors = Vec::with_capacity(5);
-        for i in 0..5 {
-            let b = off + i * 4;
-            colors.push(Color::from_rgb_u32(BigEndian::read_u32(
-                &self.mem[b..b + 4],
+    /// Makes `pick` choose themes as described by `choice`. This fails if the
+    /// theme is pinned to one that doesn't exist, or no theme matches.
+    pub fn choose(self, choice: &ThemeChoice) -> Result<Self, SketchError> {
+        if !choice.colors.is_empty() {
+            return Ok(Self {
+                chooser: Some(Arc::new(Chooser {
+                    index: None,
+                    seed: None,
+                    candidates: Vec::new(),
+                    colors: Some(choice.colors.clone()),
+                })),
+                ..self
+            });
+        }
+        if let Some(index) = choice.index {
+            if index >= self.len() {
+                return Err(SketchError::ThemeChoice(format!(
+                    "there is no theme {}, there are {}",
+                    index,
+                    self.len()
+                )));
+            }
+        }
+        let candidates = self
+            .iter()
+            .enumerate()
+            .filter(|(_, theme)| choice.filters.iter().all(|f| f.matches(theme)))
+            .map(|(ix, _)| ix)
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            let filters = choice
+                .filters
+                .iter()
+                .map(|f| f.to_string())
+                .collect::<Vec<_>>();
+            return Err(SketchError::ThemeChoice(format!(
+                "no theme matches {}",
+                filters.join(" and ")
             )));
         }
-        colors
+        Ok(Self {
+            chooser: Some(Arc::new(Chooser {
+                index: choice.index,
+                seed: choice.seed,
+                candidates,
+                colors: None,
+            })),
+            ..self
+        })
+    }
+
+    /// Records the index of each theme returned by `pick` in `picked`, which
+    /// lets the caller find out which theme a sketch ended up using.
+    pub fn record_picks(self, picked: Arc<Mutex<Option<usize>>>) -> Self {
+        Self {
+            picked: Some(picked),
+            ..self
+        }
     }
 
+    /// The colors of the theme at `idx`, or `None` if there is no such theme.
+    pub fn get(&self, idx: usize) -> Option<Vec<Color>> {
+        let off = idx.checked_mul(THEME_SIZE)?;
+        let theme = self.data.bytes().get(off..off + THEME_SIZE)?;
+        Some(
+            theme
+                .chunks_exact(4)
+                .map(|c| Color::from_rgb_u32(BigEndian::read_u32(c)))
+                .collect(),
+        )
+    }
+
+    /// Picks a theme. A value is always drawn from `rng`, even when the theme is
+    /// chosen some other way, so that the rest of a render is the same whichever
+    /// theme it ends up with.
     pub fn pick(&self, rng: &mut dyn rand::RngCore) -> (usize, Vec<Color>) {
         let ix = Uniform::new(0, self.len()).sample(rng);
-        (ix, self.get(ix))
+        // colors given in place of a theme aren't recorded since they have no
+        // index, but the draw above is still made so the geometry is the same.
+        if let Some(colors) = self.chooser.as_ref().and_then(|c| c.colors.as_ref()) {
+            return (ix, colors.clone());
+        }
+        let ix = match &self.chooser {
+            Some(chooser) => chooser.choose(ix, self.len()),
+            None => ix,
+        };
+        if let Some(picked) = &self.picked {
+            *picked.lock().unwrap() = Some(ix);
+        }
+        // themes are validated when they are loaded, so there is at least one
+        // and every index below len is present.
+        (ix, self.get(ix).unwrap())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Vec<Color>> + '_ {
+        (0..self.len()).filter_map(|ix| self.get(ix))
+    }
+
+    /// The themes in the format of a themes file.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.data.bytes()
     }
 
     pub fn len(&self) -> usize {
-        self.mem.len() / THEME_SIZE
+        self.data.bytes().len() / THEME_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 }
 
@@ -351,5 +750,7 @@ pub trait RenderOpts {
 
     fn rng(&self) -> Pcg64;
 
-    fn themes(&self) -> io::Result<Themes>;
+    fn themes(&self) -> Result<Themes, SketchError>;
+
+    fn params(&self) -> &params::Params;
 }
@@ -0,0 +1,542 @@
+use crate::{
+    common::{Format, Seed},
+    error::SketchError,
+    params::Params,
+    Color, Size,
+};
+use cairo::{ImageSurface, PdfMetadata, PdfSurface};
+use std::{fmt::Display, fs, path::Path};
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+const PDF_SIGNATURE: &[u8] = b"%PDF";
+const PDF_KEYWORDS: &[u8] = b"/Keywords (";
+const SVG_OPEN: &str = "<metadata id=\"sketches\">";
+const SVG_CLOSE: &str = "</metadata>";
+
+/// The settings needed to reproduce a rendered image. This is embedded in
+/// every image `draw` writes so that it can be replayed later.
+#[derive(Debug, Clone)]
+pub struct Metadata {
+    sketch: String,
+    seed: Seed,
+    size: Size,
+    themes: String,
+    theme: Option<usize>,
+    colors: Vec<Color>,
+    params: Vec<(String, String)>,
+    args: Vec<String>,
+}
+
+impl Metadata {
+    pub fn new(
+        sketch: &str,
+        seed: Seed,
+        size: Size,
+        themes: &str,
+        theme: Option<usize>,
+        params: Vec<(String, String)>,
+        args: Vec<String>,
+    ) -> Self {
+        Self {
+            sketch: sketch.to_owned(),
+            seed,
+            size,
+            themes: themes.to_owned(),
+            theme,
+            colors: Vec::new(),
+            params,
+            args,
+        }
+    }
+
+    /// Records the colors that were used in place of a theme from the themes
+    /// file.
+    pub fn with_colors(self, colors: Vec<Color>) -> Self {
+        Self { colors, ..self }
+    }
+
+    pub fn sketch(&self) -> &str {
+        &self.sketch
+    }
+
+    pub fn seed(&self) -> Seed {
+        self.seed
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    pub fn themes(&self) -> &str {
+        &self.themes
+    }
+
+    pub fn theme(&self) -> Option<usize> {
+        self.theme
+    }
+
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    /// The resolved values of the sketch's params.
+    pub fn params(&self) -> &[(String, String)] {
+        &self.params
+    }
+
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    fn entries(&self) -> Vec<(&str, String)> {
+        let mut entries = vec![
+            ("sketch", self.sketch.clone()),
+            ("seed", format!("{}", self.seed)),
+            ("size", format!("{}", self.size)),
+            ("themes", self.themes.clone()),
+        ];
+        if let Some(theme) = self.theme {
+            entries.push(("theme", format!("{}", theme)));
+        }
+        if !self.colors.is_empty() {
+            entries.push(("colors", Color::format_list(&self.colors)));
+        }
+        for (name, value) in self.params.iter() {
+            entries.push(("param", format!("{}={}", name, value)));
+        }
+        for arg in self.args.iter() {
+            entries.push(("arg", arg.clone()));
+        }
+        entries
+    }
+
+    fn from_entries<'a, I>(entries: I) -> Result<Self, SketchError>
+    where
+        I: Iterator<Item = (&'a str, String)>,
+    {
+        let mut sketch = None;
+        let mut seed = None;
+        let mut size = None;
+        let mut themes = None;
+        let mut theme = None;
+        let mut colors = Vec::new();
+        let mut params = Vec::new();
+        let mut args = Vec::new();
+        for (key, value) in entries {
+            match key {
+                "sketch" => sketch = Some(value),
+                "seed" => seed = Some(Seed::from_arg(&value).map_err(bad)?),
+                "size" => size = Some(Size::from_arg(&value).map_err(bad)?),
+                "themes" => themes = Some(value),
+                "theme" => theme = Some(value.parse().map_err(bad)?),
+                "colors" => colors = Color::parse_list(&value).map_err(bad)?,
+                "param" => params.push(Params::parse_override(&value).map_err(bad)?),
+                "arg" => args.push(value),
+                _ => {}
+            }
+        }
+        Ok(Self {
+            sketch: sketch.ok_or_else(|| bad("no sketch"))?,
+            seed: seed.ok_or_else(|| bad("no seed"))?,
+            size: size.ok_or_else(|| bad("no size"))?,
+            themes: themes.ok_or_else(|| bad("no themes"))?,
+            theme,
+            colors,
+            params,
+            args,
+        })
+    }
+
+    /// Encodes the metadata as a single line of space separated `key=value`
+    /// pairs, which is how it is stored in PDF and SVG documents.
+    pub fn encode(&self) -> String {
+        self.entries()
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, escape(v)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn decode(s: &str) -> Result<Self, SketchError> {
+        let entries = s
+            .split_whitespace()
+            .map(|pair| match pair.find('=') {
+                Some(ix) => Ok((&pair[..ix], unescape(&pair[ix + 1..])?)),
+                None => Err(bad(format!("expected key=value, got {}", pair))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_entries(entries.into_iter())
+    }
+
+    /// Writes the surface as a PNG to `dst` with the metadata stored in `tEXt`
+    /// chunks.
+    pub fn write_png<P: AsRef<Path>>(
+        &self,
+        surface: &ImageSurface,
+        dst: P,
+    ) -> Result<(), SketchError> {
+        let dst = dst.as_ref();
+        let mut png = Vec::new();
+        surface.write_to_png(&mut png).map_err(|e| match e {
+            cairo::IoError::Cairo(source) => SketchError::Surface {
+                path: dst.to_path_buf(),
+                source,
+            },
+            cairo::IoError::Io(source) => SketchError::Io {
+                path: dst.to_path_buf(),
+                source,
+            },
+        })?;
+
+        // cairo always emits IHDR first, so the text chunks go right after it.
+        let ihdr = chunks(&png)?
+            .into_iter()
+            .next()
+            .filter(|c| c.kind == *b"IHDR")
+            .ok_or_else(|| bad("png has no IHDR chunk"))?;
+        let mut out = Vec::with_capacity(png.len());
+        out.extend_from_slice(&png[..ihdr.end]);
+        for (key, value) in self.entries() {
+            let mut data = Vec::new();
+            data.extend_from_slice(format!("sketches:{}", key).as_bytes());
+            data.push(0);
+            data.extend_from_slice(escape(&value).as_bytes());
+            write_chunk(&mut out, b"tEXt", &data);
+        }
+        out.extend_from_slice(&png[ihdr.end..]);
+        fs::write(dst, out).map_err(|source| SketchError::Io {
+            path: dst.to_path_buf(),
+            source,
+        })
+    }
+
+    pub fn read_png(data: &[u8]) -> Result<Self, SketchError> {
+        let entries = chunks(data)?
+            .into_iter()
+            .filter(|c| c.kind == *b"tEXt")
+            .filter_map(|c| {
+                let text = &data[c.data.clone()];
+                let ix = text.iter().position(|&b| b == 0)?;
+                let key = std::str::from_utf8(&text[..ix]).ok()?;
+                let value = std::str::from_utf8(&text[ix + 1..]).ok()?;
+                Some((key.strip_prefix("sketches:")?, value))
+            })
+            .map(|(k, v)| Ok((k, unescape(v)?)))
+            .collect::<Result<Vec<_>, SketchError>>()?;
+        Self::from_entries(entries.into_iter())
+    }
+
+    /// Stores the metadata in the document information of the PDF. This has to
+    /// be called before the surface is finished.
+    pub fn apply_to_pdf(&self, surface: &PdfSurface) -> Result<(), SketchError> {
+        surface.set_metadata(
+            PdfMetadata::Title,
+            &format!("{} {}", self.sketch, self.seed),
+        )?;
+        surface.set_metadata(PdfMetadata::Keywords, &self.encode())?;
+        Ok(())
+    }
+
+    pub fn read_pdf(data: &[u8]) -> Result<Self, SketchError> {
+        let start = data
+            .windows(PDF_KEYWORDS.len())
+            .position(|w| w == PDF_KEYWORDS)
+            .ok_or_else(|| bad("pdf has no metadata"))?
+            + PDF_KEYWORDS.len();
+        let len = data[start..]
+            .iter()
+            .position(|&b| b == b')')
+            .ok_or_else(|| bad("pdf metadata is not terminated"))?;
+        Self::decode(std::str::from_utf8(&data[start..start + len]).map_err(bad)?)
+    }
+
+    /// Adds a `<metadata>` element to an SVG document that has already been
+    /// written to `dst`.
+    pub fn apply_to_svg<P: AsRef<Path>>(&self, dst: P) -> Result<(), SketchError> {
+        let dst = dst.as_ref();
+        let io = |source| SketchError::Io {
+            path: dst.to_path_buf(),
+            source,
+        };
+        let svg = fs::read_to_string(dst).map_err(io)?;
+        let ix = svg
+            .find("<svg")
+            .and_then(|ix| svg[ix..].find('>').map(|j| ix + j + 1))
+            .ok_or_else(|| bad("svg has no root element").at(dst.to_path_buf()))?;
+        fs::write(
+            dst,
+            format!(
+                "{}\n{}{}{}{}",
+                &svg[..ix],
+                SVG_OPEN,
+                self.encode(),
+                SVG_CLOSE,
+                &svg[ix..]
+            ),
+        )
+        .map_err(io)
+    }
+
+    pub fn read_svg(data: &[u8]) -> Result<Self, SketchError> {
+        let svg = std::str::from_utf8(data).map_err(bad)?;
+        let start = svg
+            .find(SVG_OPEN)
+            .ok_or_else(|| bad("svg has no metadata"))?
+            + SVG_OPEN.len();
+        let len = svg[start..]
+            .find(SVG_CLOSE)
+            .ok_or_else(|| bad("svg metadata is not terminated"))?;
+        Self::decode(&svg[start..start + len])
+    }
+
+    /// Reads the metadata from a PNG, PDF or SVG file written by `draw`.
+    pub fn read<P: AsRef<Path>>(src: P) -> Result<Self, SketchError> {
+        Self::read_with_format(src).map(|(meta, _)| meta)
+    }
+
+    /// Reads the metadata from a file written by `draw`, along with the format
+    /// of the file, which is told from its contents rather than its name.
+    pub fn read_with_format<P: AsRef<Path>>(src: P) -> Result<(Self, Format), SketchError> {
+        let src = src.as_ref();
+        let data = fs::read(src).map_err(|source| SketchError::Io {
+            path: src.to_path_buf(),
+            source,
+        })?;
+        let format = if data.starts_with(&PNG_SIGNATURE) {
+            Format::Png
+        } else if data.starts_with(PDF_SIGNATURE) {
+            Format::Pdf
+        } else {
+            Format::Svg
+        };
+        let res = match format {
+            Format::Png => Self::read_png(&data),
+            Format::Pdf => Self::read_pdf(&data),
+            Format::Svg => Self::read_svg(&data),
+        };
+        res.map(|meta| (meta, format))
+            .map_err(|e| e.at(src.to_path_buf()))
+    }
+}
+
+struct Chunk {
+    kind: [u8; 4],
+    data: std::ops::Range<usize>,
+    end: usize,
+}
+
+fn bad<E: Display>(e: E) -> SketchError {
+    SketchError::metadata(e.to_string())
+}
+
+fn chunks(png: &[u8]) -> Result<Vec<Chunk>, SketchError> {
+    if !png.starts_with(&PNG_SIGNATURE) {
+        return Err(bad("not a png"));
+    }
+    let mut chunks = Vec::new();
+    let mut off = PNG_SIGNATURE.len();
+    while off + 8 <= png.len() {
+        let len = u32::from_be_bytes(png[off..off + 4].try_into().map_err(bad)?) as usize;
+        let start = off + 8;
+        let end = start + len + 4;
+        if end > png.len() {
+            return Err(bad("png chunk is truncated"));
+        }
+        chunks.push(Chunk {
+            kind: png[off + 4..off + 8].try_into().map_err(bad)?,
+            data: start..start + len,
+            end,
+        });
+        off = end;
+    }
+    Ok(chunks)
+}
+
+fn write_chunk(w: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    w.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    w.extend_from_slice(kind);
+    w.extend_from_slice(data);
+    w.extend_from_slice(&crc32(&[kind, data]).to_be_bytes());
+}
+
+fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut crc = 0xffffffffu32;
+    for &b in parts.iter().flat_map(|p| p.iter()) {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+// Values are percent-encoded so that the metadata is plain ASCII with no
+// whitespace or characters that need quoting in PDF strings or XML.
+fn escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for &b in s.as_bytes() {
+        if b.is_ascii_alphanumeric() || b"-_.~/:+,@".contains(&b) {
+            res.push(b as char);
+        } else {
+            res.push_str(&format!("%{:02X}", b));
+        }
+    }
+    res
+}
+
+fn unescape(s: &str) -> Result<String, SketchError> {
+    let mut res = Vec::with_capacity(s.len());
+    let b = s.as_bytes();
+    let mut i = 0;
+    while i < b.len() {
+        if b[i] == b'%' {
+            let hex = s.get(i + 1..i + 3).ok_or_else(|| bad("truncated escape"))?;
+            res.push(u8::from_str_radix(hex, 16).map_err(bad)?);
+            i += 3;
+        } else {
+            res.push(b[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(res).map_err(bad)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cairo::{Context, Format as ImageFormat, SvgSurface};
+    use std::path::PathBuf;
+
+    fn meta() -> Metadata {
+        Metadata::new(
+            "a",
+            Seed::new(0xc0ffee),
+            Size::new(320, 200),
+            "some dir/themes 100%.bin",
+            Some(3),
+            vec![("nx".to_owned(), "12".to_owned())],
+            vec!["--mode=wavy lines".to_owned()],
+        )
+        .with_colors(vec![Color::from_rgb(0x11, 0x22, 0x33), Color::white()])
+    }
+
+    fn assert_same(a: &Metadata, b: &Metadata) {
+        assert_eq!(a.encode(), b.encode());
+        assert_eq!(b.sketch(), "a");
+        assert_eq!(b.seed().to_string(), "00c0ffee");
+        assert_eq!(b.size().to_string(), "320x200");
+        assert_eq!(b.themes(), "some dir/themes 100%.bin");
+        assert_eq!(b.theme(), Some(3));
+        assert_eq!(
+            b.colors().iter().map(Color::to_rgb_u32).collect::<Vec<_>>(),
+            vec![0x112233, 0xffffff]
+        );
+        assert_eq!(b.params(), a.params());
+        assert_eq!(b.args(), a.args());
+    }
+
+    fn temp(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sketches-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(&[b"123456789"]), 0xcbf43926);
+        assert_eq!(crc32(&[b"1234", b"56789"]), 0xcbf43926);
+        assert_eq!(crc32(&[]), 0);
+    }
+
+    #[test]
+    fn escape_round_trip() {
+        for s in ["plain", "two words", "100%", "a=b", "ünïcödé ✓", "%41", ""] {
+            let e = escape(s);
+            assert!(e.bytes().all(|b| b.is_ascii_graphic() && b != b'='));
+            assert_eq!(unescape(&e).unwrap(), s);
+        }
+        assert_eq!(escape("a b%c=d"), "a%20b%25c%3Dd");
+        assert!(unescape("%4").is_err());
+        assert!(unescape("%zz").is_err());
+        assert!(unescape("%FF").is_err());
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        let m = meta();
+        assert_same(&m, &Metadata::decode(&m.encode()).unwrap());
+        assert!(Metadata::decode("sketch=a seed").is_err());
+        assert!(Metadata::decode("sketch=a size=1x1 themes=t").is_err());
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let dst = temp("meta.png");
+        let surface = ImageSurface::create(ImageFormat::ARgb32, 4, 3).unwrap();
+        meta().write_png(&surface, &dst).unwrap();
+        let data = fs::read(&dst).unwrap();
+        fs::remove_file(&dst).unwrap();
+
+        // every chunk, including the inserted ones, keeps a valid checksum.
+        let chunks = chunks(&data).unwrap();
+        assert_eq!(&chunks[0].kind, b"IHDR");
+        assert_eq!(&chunks[1].kind, b"tEXt");
+        assert_eq!(&chunks.last().unwrap().kind, b"IEND");
+        for c in chunks.iter() {
+            let crc = u32::from_be_bytes(data[c.data.end..c.end].try_into().unwrap());
+            assert_eq!(crc, crc32(&[&c.kind, &data[c.data.clone()]]));
+        }
+        assert_same(&meta(), &Metadata::read_png(&data).unwrap());
+    }
+
+    #[test]
+    fn pdf_round_trip() {
+        let dst = temp("meta.pdf");
+        let surface = PdfSurface::new(40.0, 30.0, &dst).unwrap();
+        meta().apply_to_pdf(&surface).unwrap();
+        let ctx = Context::new(&surface).unwrap();
+        ctx.paint().unwrap();
+        drop(ctx);
+        surface.finish();
+        let (read, format) = Metadata::read_with_format(&dst).unwrap();
+        fs::remove_file(&dst).unwrap();
+        assert_eq!(format, Format::Pdf);
+        assert_same(&meta(), &read);
+    }
+
+    #[test]
+    fn svg_round_trip() {
+        let dst = temp("meta.svg");
+        let surface = SvgSurface::new(40.0, 30.0, Some(&dst)).unwrap();
+        let ctx = Context::new(&surface).unwrap();
+        ctx.paint().unwrap();
+        drop(ctx);
+        surface.finish();
+        meta().apply_to_svg(&dst).unwrap();
+        let (read, format) = Metadata::read_with_format(&dst).unwrap();
+        fs::remove_file(&dst).unwrap();
+        assert_eq!(format, Format::Svg);
+        assert_same(&meta(), &read);
+    }
+
+    #[test]
+    fn sniffs_format_from_contents() {
+        // the extension is ignored; a png named .svg is still read as a png.
+        let dst = temp("png-named.svg");
+        let surface = ImageSurface::create(ImageFormat::ARgb32, 2, 2).unwrap();
+        meta().write_png(&surface, &dst).unwrap();
+        let (read, format) = Metadata::read_with_format(&dst).unwrap();
+        fs::remove_file(&dst).unwrap();
+        assert_eq!(format, Format::Png);
+        assert_same(&meta(), &read);
+
+        let dst = temp("plain.txt");
+        fs::write(&dst, "no metadata here").unwrap();
+        let res = Metadata::read_with_format(&dst);
+        fs::remove_file(&dst).unwrap();
+        assert!(res.is_err());
+        assert!(Metadata::read_png(b"\x89PNG\r\n\x1a\n\0\0\0\xffIHDR").is_err());
+    }
+}
@@ -0,0 +1,886 @@
+use crate::Point;
+use rand::{RngCore, SeedableRng};
+use rand_pcg::Pcg64;
+
+// Skew and unskew factors for simplex noise, written out so that they are the
+// same everywhere rather than depending on how sqrt is computed.
+const F2: f64 = 0.366_025_403_784_438_6; // (sqrt(3) - 1) / 2
+const G2: f64 = 0.211_324_865_405_187_1; // (3 - sqrt(3)) / 6
+const F3: f64 = 1.0 / 3.0;
+const G3: f64 = 1.0 / 6.0;
+const F4: f64 = 0.309_016_994_374_947_45; // (sqrt(5) - 1) / 4
+const G4: f64 = 0.138_196_601_125_010_5; // (5 - sqrt(5)) / 20
+
+const GRAD2: [[f64; 2]; 8] = [
+    [1.0, 1.0],
+    [-1.0, 1.0],
+    [1.0, -1.0],
+    [-1.0, -1.0],
+    [1.0, 0.0],
+    [-1.0, 0.0],
+    [0.0, 1.0],
+    [0.0, -1.0],
+];
+
+// The midpoints of the edges of a cube, with four repeated to make 16.
+const GRAD3: [[f64; 3]; 16] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [0.0, -1.0, 1.0],
+    [0.0, -1.0, -1.0],
+];
+
+// The midpoints of the edges of a tesseract.
+const GRAD4: [[f64; 4]; 32] = [
+    [0.0, 1.0, 1.0, 1.0],
+    [0.0, 1.0, 1.0, -1.0],
+    [0.0, 1.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0, -1.0],
+    [0.0, -1.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0, 1.0],
+    [0.0, -1.0, -1.0, -1.0],
+    [1.0, 0.0, 1.0, 1.0],
+    [1.0, 0.0, 1.0, -1.0],
+    [1.0, 0.0, -1.0, 1.0],
+    [1.0, 0.0, -1.0, -1.0],
+    [-1.0, 0.0, 1.0, 1.0],
+    [-1.0, 0.0, 1.0, -1.0],
+    [-1.0, 0.0, -1.0, 1.0],
+    [-1.0, 0.0, -1.0, -1.0],
+    [1.0, 1.0, 0.0, 1.0],
+    [1.0, 1.0, 0.0, -1.0],
+    [1.0, -1.0, 0.0, 1.0],
+    [1.0, -1.0, 0.0, -1.0],
+    [-1.0, 1.0, 0.0, 1.0],
+    [-1.0, 1.0, 0.0, -1.0],
+    [-1.0, -1.0, 0.0, 1.0],
+    [-1.0, -1.0, 0.0, -1.0],
+    [1.0, 1.0, 1.0, 0.0],
+    [1.0, 1.0, -1.0, 0.0],
+    [1.0, -1.0, 1.0, 0.0],
+    [1.0, -1.0, -1.0, 0.0],
+    [-1.0, 1.0, 1.0, 0.0],
+    [-1.0, 1.0, -1.0, 0.0],
+    [-1.0, -1.0, 1.0, 0.0],
+    [-1.0, -1.0, -1.0, 0.0],
+];
+
+/// Smooth noise in 2, 3 and 4 dimensions. Values are roughly between -1 and 1
+/// unless the type says otherwise, and the features are about 1 unit apart,
+/// so coordinates are usually scaled down before they are passed in.
+///
+/// Noise is built only from a permutation that is shuffled with `next_u32`,
+/// and arithmetic that IEEE 754 defines exactly, so the same seed gives the
+/// same values on every platform.
+pub trait Noise {
+    fn noise2(&self, x: f64, y: f64) -> f64;
+
+    fn noise3(&self, x: f64, y: f64, z: f64) -> f64;
+
+    fn noise4(&self, x: f64, y: f64, z: f64, w: f64) -> f64;
+
+    fn at(&self, p: &Point) -> f64 {
+        self.noise2(p.x(), p.y())
+    }
+
+    /// 2D noise that changes with `t` and comes back to where it started when
+    /// `t` goes from 0 to 1, for looping animations. It walks a circle of
+    /// `radius` through the other two dimensions of 4D noise, so larger radii
+    /// change faster. This uses sin and cos, so unlike the rest of the noise
+    /// it may differ in the last bits between platforms.
+    fn looped(&self, x: f64, y: f64, t: f64, radius: f64) -> f64 {
+        let (s, c) = (t * std::f64::consts::TAU).sin_cos();
+        self.noise4(x, y, radius * c, radius * s)
+    }
+}
+
+// A permutation of 0..256, twice over so that lookups of a sum of two
+// entries don't need wrapping.
+#[derive(Clone)]
+struct Perm([u8; 512]);
+
+impl Perm {
+    fn new(rng: &mut dyn RngCore) -> Self {
+        let mut p = [0u8; 256];
+        for (i, v) in p.iter_mut().enumerate() {
+            *v = i as u8;
+        }
+        for i in (1..256).rev() {
+            // scales a u32 down to 0..=i without depending on the width of
+            // usize, as gen_range would.
+            let j = ((rng.next_u32() as u64 * (i as u64 + 1)) >> 32) as usize;
+            p.swap(i, j);
+        }
+        let mut perm = [0u8; 512];
+        perm[..256].copy_from_slice(&p);
+        perm[256..].copy_from_slice(&p);
+        Perm(perm)
+    }
+
+    fn at(&self, i: usize) -> usize {
+        self.0[i] as usize
+    }
+
+    // Hashes the lattice point `c`, whose coordinates are each in 0..256.
+    fn hash(&self, c: &[usize]) -> usize {
+        c.iter().rev().fold(0, |h, &v| self.at(v + h))
+    }
+}
+
+impl std::fmt::Debug for Perm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Perm({:?}..)", &self.0[..8])
+    }
+}
+
+// The quintic that eases between lattice points so that the noise has smooth
+// first and second derivatives.
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lattice_cell(v: f64) -> usize {
+    (v.floor() as i64 & 255) as usize
+}
+
+// Interpolates the values that `corner` gives at the 2^N corners of the
+// lattice cell that holds `p`. `corner` is passed the hash of a corner and the
+// offset of `p` from it.
+fn lattice<const N: usize>(
+    perm: &Perm,
+    p: [f64; N],
+    corner: impl Fn(usize, &[f64; N]) -> f64,
+) -> f64 {
+    let cell = p.map(lattice_cell);
+    let frac = p.map(|v| v - v.floor());
+    let weights = frac.map(fade);
+    let mut sum = 0.0;
+    for mask in 0..1usize << N {
+        let mut c = [0; N];
+        let mut offset = [0.0; N];
+        let mut weight = 1.0;
+        for d in 0..N {
+            let bit = (mask >> d) & 1;
+            c[d] = (cell[d] + bit) & 255;
+            offset[d] = frac[d] - bit as f64;
+            weight *= if bit == 1 {
+                weights[d]
+            } else {
+                1.0 - weights[d]
+            };
+        }
+        sum += weight * corner(perm.hash(&c), &offset);
+    }
+    sum
+}
+
+fn dot(g: &[f64], v: &[f64]) -> f64 {
+    g.iter().zip(v.iter()).map(|(a, b)| a * b).sum()
+}
+
+/// Ken Perlin's improved gradient noise.
+#[derive(Debug, Clone)]
+pub struct Perlin {
+    perm: Perm,
+}
+
+impl Perlin {
+    pub fn new(rng: &mut dyn RngCore) -> Self {
+        Self {
+            perm: Perm::new(rng),
+        }
+    }
+
+    pub fn seeded(seed: u64) -> Self {
+        Self::new(&mut Pcg64::seed_from_u64(seed))
+    }
+}
+
+impl Noise for Perlin {
+    fn noise2(&self, x: f64, y: f64) -> f64 {
+        lattice(&self.perm, [x, y], |h, o| dot(&GRAD2[h & 7], o))
+    }
+
+    fn noise3(&self, x: f64, y: f64, z: f64) -> f64 {
+        lattice(&self.perm, [x, y, z], |h, o| dot(&GRAD3[h & 15], o))
+    }
+
+    fn noise4(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        lattice(&self.perm, [x, y, z, w], |h, o| dot(&GRAD4[h & 31], o)) * 0.9
+    }
+}
+
+/// Noise made by easing between random values at the lattice points. It is
+/// blockier than gradient noise, with features along the lattice.
+#[derive(Debug, Clone)]
+pub struct Value {
+    perm: Perm,
+}
+
+impl Value {
+    pub fn new(rng: &mut dyn RngCore) -> Self {
+        Self {
+            perm: Perm::new(rng),
+        }
+    }
+
+    pub fn seeded(seed: u64) -> Self {
+        Self::new(&mut Pcg64::seed_from_u64(seed))
+    }
+
+    fn value(h: usize, _: &[f64]) -> f64 {
+        h as f64 / 127.5 - 1.0
+    }
+}
+
+impl Noise for Value {
+    fn noise2(&self, x: f64, y: f64) -> f64 {
+        lattice(&self.perm, [x, y], |h, o| Self::value(h, o))
+    }
+
+    fn noise3(&self, x: f64, y: f64, z: f64) -> f64 {
+        lattice(&self.perm, [x, y, z], |h, o| Self::value(h, o))
+    }
+
+    fn noise4(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        lattice(&self.perm, [x, y, z, w], |h, o| Self::value(h, o))
+    }
+}
+
+/// Simplex noise, which sums gradients from the corners of a simplex rather
+/// than a cube. It has fewer directional artifacts than `Perlin` and is
+/// cheaper in higher dimensions.
+#[derive(Debug, Clone)]
+pub struct Simplex {
+    perm: Perm,
+}
+
+impl Simplex {
+    pub fn new(rng: &mut dyn RngCore) -> Self {
+        Self {
+            perm: Perm::new(rng),
+        }
+    }
+
+    pub fn seeded(seed: u64) -> Self {
+        Self::new(&mut Pcg64::seed_from_u64(seed))
+    }
+}
+
+// The contribution of a simplex corner whose gradient is `g`, at offset `o`
+// from it, which falls off to nothing at a distance of sqrt(r2).
+fn falloff(r2: f64, g: &[f64], o: &[f64]) -> f64 {
+    let t = r2 - dot(o, o);
+    if t < 0.0 {
+        0.0
+    } else {
+        let t = t * t;
+        t * t * dot(g, o)
+    }
+}
+
+impl Noise for Simplex {
+    fn noise2(&self, x: f64, y: f64) -> f64 {
+        let s = (x + y) * F2;
+        let (i, j) = ((x + s).floor(), (y + s).floor());
+        let t = (i + j) * G2;
+        let o0 = [x - (i - t), y - (j - t)];
+        let (i1, j1) = if o0[0] > o0[1] { (1, 0) } else { (0, 1) };
+        let o1 = [o0[0] - i1 as f64 + G2, o0[1] - j1 as f64 + G2];
+        let o2 = [o0[0] - 1.0 + 2.0 * G2, o0[1] - 1.0 + 2.0 * G2];
+        let (ii, jj) = (lattice_cell(i), lattice_cell(j));
+        let p = &self.perm;
+        let g0 = p.hash(&[ii, jj]) & 7;
+        let g1 = p.hash(&[ii + i1, jj + j1]) & 7;
+        let g2 = p.hash(&[ii + 1, jj + 1]) & 7;
+        70.0 * (falloff(0.5, &GRAD2[g0], &o0)
+            + falloff(0.5, &GRAD2[g1], &o1)
+            + falloff(0.5, &GRAD2[g2], &o2))
+    }
+
+    fn noise3(&self, x: f64, y: f64, z: f64) -> f64 {
+        let s = (x + y + z) * F3;
+        let (i, j, k) = ((x + s).floor(), (y + s).floor(), (z + s).floor());
+        let t = (i + j + k) * G3;
+        let o0 = [x - (i - t), y - (j - t), z - (k - t)];
+        // the corners of the simplex after the first, from the order of the
+        // offsets.
+        let (a, b) = if o0[0] >= o0[1] {
+            if o0[1] >= o0[2] {
+                ([1, 0, 0], [1, 1, 0])
+            } else if o0[0] >= o0[2] {
+                ([1, 0, 0], [1, 0, 1])
+            } else {
+                ([0, 0, 1], [1, 0, 1])
+            }
+        } else if o0[1] < o0[2] {
+            ([0, 0, 1], [0, 1, 1])
+        } else if o0[0] < o0[2] {
+            ([0, 1, 0], [0, 1, 1])
+        } else {
+            ([0, 1, 0], [1, 1, 0])
+        };
+        let c = [lattice_cell(i), lattice_cell(j), lattice_cell(k)];
+        let mut sum = 0.0;
+        for (n, corner) in [[0, 0, 0], a, b, [1, 1, 1]].iter().enumerate() {
+            let o = [0, 1, 2].map(|d| o0[d] - corner[d] as f64 + n as f64 * G3);
+            let g = self.perm.hash(&[0, 1, 2].map(|d| c[d] + corner[d])) & 15;
+            sum += falloff(0.6, &GRAD3[g], &o);
+        }
+        32.0 * sum
+    }
+
+    fn noise4(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        let p = [x, y, z, w];
+        let s = (x + y + z + w) * F4;
+        let cell = p.map(|v| (v + s).floor());
+        let t = cell.iter().sum::<f64>() * G4;
+        let o0 = [0, 1, 2, 3].map(|d| p[d] - (cell[d] - t));
+        // each coordinate steps up at the corner given by how many of the
+        // others its offset is larger than.
+        let mut rank = [0; 4];
+        for a in 0..4 {
+            for b in a + 1..4 {
+                if o0[a] > o0[b] {
+                    rank[a] += 1;
+                } else {
+                    rank[b] += 1;
+                }
+            }
+        }
+        let c = cell.map(lattice_cell);
+        let mut sum = 0.0;
+        for n in 0..5 {
+            let corner = rank.map(|r| usize::from(r + n >= 4));
+            let o = [0, 1, 2, 3].map(|d| o0[d] - corner[d] as f64 + n as f64 * G4);
+            let g = self.perm.hash(&[0, 1, 2, 3].map(|d| c[d] + corner[d])) & 31;
+            sum += falloff(0.6, &GRAD4[g], &o);
+        }
+        27.0 * sum
+    }
+}
+
+// 24 unit directions, 15 degrees apart and offset from the axes, which is the
+// 2D gradient set of OpenSimplex2.
+const GRAD2_24: [[f64; 2]; 24] = [
+    [0.991_444_861_373_810_4, 0.130_526_192_220_051_6],
+    [0.923_879_532_511_286_7, 0.382_683_432_365_089_8],
+    [0.793_353_340_291_235_2, 0.608_761_429_008_720_7],
+    [0.608_761_429_008_720_7, 0.793_353_340_291_235_2],
+    [0.382_683_432_365_089_8, 0.923_879_532_511_286_7],
+    [0.130_526_192_220_051_6, 0.991_444_861_373_810_4],
+    [-0.130_526_192_220_051_6, 0.991_444_861_373_810_4],
+    [-0.382_683_432_365_089_8, 0.923_879_532_511_286_7],
+    [-0.608_761_429_008_720_7, 0.793_353_340_291_235_2],
+    [-0.793_353_340_291_235_2, 0.608_761_429_008_720_7],
+    [-0.923_879_532_511_286_7, 0.382_683_432_365_089_8],
+    [-0.991_444_861_373_810_4, 0.130_526_192_220_051_6],
+    [-0.991_444_861_373_810_4, -0.130_526_192_220_051_6],
+    [-0.923_879_532_511_286_7, -0.382_683_432_365_089_8],
+    [-0.793_353_340_291_235_2, -0.608_761_429_008_720_7],
+    [-0.608_761_429_008_720_7, -0.793_353_340_291_235_2],
+    [-0.382_683_432_365_089_8, -0.923_879_532_511_286_7],
+    [-0.130_526_192_220_051_6, -0.991_444_861_373_810_4],
+    [0.130_526_192_220_051_6, -0.991_444_861_373_810_4],
+    [0.382_683_432_365_089_8, -0.923_879_532_511_286_7],
+    [0.608_761_429_008_720_7, -0.793_353_340_291_235_2],
+    [0.793_353_340_291_235_2, -0.608_761_429_008_720_7],
+    [0.923_879_532_511_286_7, -0.382_683_432_365_089_8],
+    [0.991_444_861_373_810_4, -0.130_526_192_220_051_6],
+];
+
+/// OpenSimplex2, Kurt Spencer's successor to simplex noise. 2D is simplex on
+/// the triangular lattice with more gradient directions, 3D sums over the two
+/// cubic lattices of a body-centered cubic lattice in a rotated frame, and 4D
+/// over five shifted copies of the 4D simplex lattice. The 3D and 4D noise
+/// has fewer straight-line artifacts than `Simplex`. It uses the gradients of
+/// `Simplex` there, and every corner falls off within sqrt(0.5), which keeps
+/// the noise continuous with one or two corners from each lattice.
+#[derive(Debug, Clone)]
+pub struct OpenSimplex {
+    perm: Perm,
+}
+
+impl OpenSimplex {
+    pub fn new(rng: &mut dyn RngCore) -> Self {
+        Self {
+            perm: Perm::new(rng),
+        }
+    }
+
+    pub fn seeded(seed: u64) -> Self {
+        Self::new(&mut Pcg64::seed_from_u64(seed))
+    }
+
+    // Hashes lattice point `c` of copy `copy` of the lattice, so that each
+    // copy gets its own gradients.
+    fn hash<const N: usize>(&self, c: [i64; N], copy: usize) -> usize {
+        let mut h = [copy; 5];
+        for d in 0..N {
+            h[d] = (c[d] & 255) as usize;
+        }
+        self.perm.hash(&h[..=N])
+    }
+}
+
+impl Noise for OpenSimplex {
+    fn noise2(&self, x: f64, y: f64) -> f64 {
+        let s = (x + y) * F2;
+        let (i, j) = ((x + s).floor(), (y + s).floor());
+        let t = (i + j) * G2;
+        let o0 = [x - (i - t), y - (j - t)];
+        let (i1, j1) = if o0[1] > o0[0] { (0, 1) } else { (1, 0) };
+        let o1 = [o0[0] - i1 as f64 + G2, o0[1] - j1 as f64 + G2];
+        let o2 = [o0[0] - 1.0 + 2.0 * G2, o0[1] - 1.0 + 2.0 * G2];
+        let (i, j) = (i as i64, j as i64);
+        let mut sum = 0.0;
+        for (c, o) in [([i, j], o0), ([i + i1, j + j1], o1), ([i + 1, j + 1], o2)] {
+            sum += falloff(0.5, &GRAD2_24[self.hash(c, 0) % 24], &o);
+        }
+        99.0 * sum
+    }
+
+    fn noise3(&self, x: f64, y: f64, z: f64) -> f64 {
+        // turns the main diagonal of the cube onto the main diagonal of the
+        // lattice, a rotation that is its own inverse.
+        let r = (x + y + z) * (2.0 / 3.0);
+        let p = [r - x, r - y, r - z];
+        let mut base = p.map(|v| v.round() as i64);
+        let mut o = [0, 1, 2].map(|d| p[d] - base[d] as f64);
+        let mut sum = 0.0;
+        for copy in 0..2 {
+            // the nearest point of this cubic lattice, and its neighbor along
+            // the axis the point is furthest out on. The corners fall off
+            // within sqrt(0.5), which no other point of the lattice is.
+            let g = self.hash(base, copy) & 15;
+            sum += falloff(0.5, &GRAD3[g], &o);
+            let abs = o.map(f64::abs);
+            let d = if abs[0] >= abs[1] && abs[0] >= abs[2] {
+                0
+            } else if abs[1] >= abs[2] {
+                1
+            } else {
+                2
+            };
+            let step = if o[d] > 0.0 { 1 } else { -1 };
+            let mut c = base;
+            c[d] += step;
+            let mut on = o;
+            on[d] -= step as f64;
+            sum += falloff(0.5, &GRAD3[self.hash(c, copy) & 15], &on);
+            // the other lattice is offset by a half along each axis, with
+            // its points numbered by the corner of the cell above them.
+            for d in 0..3 {
+                let half = if o[d] > 0.0 { 0.5 } else { -0.5 };
+                o[d] -= half;
+                if half > 0.0 {
+                    base[d] += 1;
+                }
+            }
+        }
+        76.0 * sum
+    }
+
+    fn noise4(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        let s = (x + y + z + w) * -G4;
+        let skewed = [x + s, y + s, z + s, w + s];
+        let mut base = skewed.map(|v| v.floor() as i64);
+        let mut si = [0, 1, 2, 3].map(|d| skewed[d] - base[d] as f64);
+        // copy k of the lattice is shifted by k / 5 along the main diagonal
+        // of the skewed space. Start from the copy whose cell holds the point
+        // and step down through the others.
+        let start = (si.iter().sum::<f64>() * 1.25) as usize;
+        for v in si.iter_mut() {
+            *v -= start as f64 * 0.2;
+        }
+        let mut sum = 0.0;
+        for n in 0..5 {
+            // the nearest point of this copy, which is the only one within
+            // the sqrt(0.5) that the corners fall off in: the base corner of
+            // the cell, or the corner one step along the axis the point is
+            // furthest along.
+            let score = 1.0 - si.iter().sum::<f64>();
+            let mut d = 0;
+            for e in 1..4 {
+                if si[e] > si[d] {
+                    d = e;
+                }
+            }
+            if si[d] >= score {
+                base[d] += 1;
+                si[d] -= 1.0;
+            }
+            let t = si.iter().sum::<f64>() * F4;
+            let o = si.map(|v| v + t);
+            let copy = (start + 5 - n) % 5;
+            sum += falloff(0.5, &GRAD4[self.hash(base, copy) & 31], &o);
+            for v in si.iter_mut() {
+                *v += 0.2;
+            }
+            if n == start {
+                for b in base.iter_mut() {
+                    *b -= 1;
+                }
+            }
+        }
+        62.0 * sum
+    }
+}
+
+/// How the octaves of a `Fractal` are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Octaves {
+    /// Fractal Brownian motion, the plain sum of the octaves, from -1 to 1.
+    Fbm,
+    /// The sum of the absolute values of the octaves, from 0 to 1, which has
+    /// sharp creases where the noise crosses zero.
+    Turbulence,
+    /// Turbulence turned upside down and squared, from 0 to 1, which gives
+    /// sharp ridges.
+    Ridged,
+}
+
+/// Noise summed over octaves, each of which is `lacunarity` times the
+/// frequency and `gain` times the amplitude of the one before.
+#[derive(Debug, Clone)]
+pub struct Fractal<N> {
+    noise: N,
+    kind: Octaves,
+    octaves: usize,
+    lacunarity: f64,
+    gain: f64,
+}
+
+impl<N: Noise> Fractal<N> {
+    pub fn new(noise: N, kind: Octaves, octaves: usize) -> Self {
+        Self {
+            noise,
+            kind,
+            octaves,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    pub fn fbm(noise: N, octaves: usize) -> Self {
+        Self::new(noise, Octaves::Fbm, octaves)
+    }
+
+    pub fn turbulence(noise: N, octaves: usize) -> Self {
+        Self::new(noise, Octaves::Turbulence, octaves)
+    }
+
+    pub fn ridged(noise: N, octaves: usize) -> Self {
+        Self::new(noise, Octaves::Ridged, octaves)
+    }
+
+    pub fn with_lacunarity(self, lacunarity: f64) -> Self {
+        Self { lacunarity, ..self }
+    }
+
+    pub fn with_gain(self, gain: f64) -> Self {
+        Self { gain, ..self }
+    }
+
+    // Sums `octave`, which is given the frequency and an offset that moves
+    // each octave to a different part of the noise, so that they don't all
+    // line up at the origin.
+    fn sum(&self, octave: impl Fn(f64, f64) -> f64) -> f64 {
+        let (mut freq, mut amp) = (1.0, 1.0);
+        let (mut sum, mut total) = (0.0, 0.0);
+        for i in 0..self.octaves {
+            let n = octave(freq, i as f64 * 17.31);
+            sum += amp
+                * match self.kind {
+                    Octaves::Fbm => n,
+                    Octaves::Turbulence => n.abs(),
+                    Octaves::Ridged => (1.0 - n.abs()).powi(2),
+                };
+            total += amp;
+            freq *= self.lacunarity;
+            amp *= self.gain;
+        }
+        if total == 0.0 {
+            0.0
+        } else {
+            sum / total
+        }
+    }
+}
+
+impl<N: Noise> Noise for Fractal<N> {
+    fn noise2(&self, x: f64, y: f64) -> f64 {
+        self.sum(|f, o| self.noise.noise2(x * f + o, y * f + o))
+    }
+
+    fn noise3(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.sum(|f, o| self.noise.noise3(x * f + o, y * f + o, z * f + o))
+    }
+
+    fn noise4(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        self.sum(|f, o| {
+            self.noise
+                .noise4(x * f + o, y * f + o, z * f + o, w * f + o)
+        })
+    }
+}
+
+// Where the warp is sampled for the displacement along each axis, so that the
+// axes are displaced independently.
+const WARP_OFFSETS: [f64; 4] = [0.0, 5.2, 1.3, 8.7];
+
+/// Domain warping, which looks up `noise` at coordinates that `warp` has
+/// displaced by up to `amount`, giving swirled, organic shapes.
+#[derive(Debug, Clone)]
+pub struct Warp<N, W> {
+    noise: N,
+    warp: W,
+    amount: f64,
+}
+
+impl<N: Noise, W: Noise> Warp<N, W> {
+    pub fn new(noise: N, warp: W, amount: f64) -> Self {
+        Self {
+            noise,
+            warp,
+            amount,
+        }
+    }
+}
+
+impl<N: Noise, W: Noise> Noise for Warp<N, W> {
+    fn noise2(&self, x: f64, y: f64) -> f64 {
+        let d = WARP_OFFSETS.map(|o| self.amount * self.warp.noise2(x + o, y + o));
+        self.noise.noise2(x + d[0], y + d[1])
+    }
+
+    fn noise3(&self, x: f64, y: f64, z: f64) -> f64 {
+        let d = WARP_OFFSETS.map(|o| self.amount * self.warp.noise3(x + o, y + o, z + o));
+        self.noise.noise3(x + d[0], y + d[1], z + d[2])
+    }
+
+    fn noise4(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        let d = WARP_OFFSETS.map(|o| self.amount * self.warp.noise4(x + o, y + o, z + o, w + o));
+        self.noise.noise4(x + d[0], y + d[1], z + d[2], w + d[3])
+    }
+}
+
+impl<N: Noise + ?Sized> Noise for &N {
+    fn noise2(&self, x: f64, y: f64) -> f64 {
+        (**self).noise2(x, y)
+    }
+
+    fn noise3(&self, x: f64, y: f64, z: f64) -> f64 {
+        (**self).noise3(x, y, z)
+    }
+
+    fn noise4(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        (**self).noise4(x, y, z, w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POINTS: [[f64; 4]; 3] = [
+        [0.5, 1.25, 2.75, 0.1],
+        [-3.3, 7.9, 0.6, -2.2],
+        [12.1, -0.45, 5.05, 9.9],
+    ];
+
+    // The 2D, 3D and 4D values at each of POINTS, which pin down the
+    // permutation drawn from the seed as well as the noise functions.
+    fn check(noise: &dyn Noise, expected: [[f64; 3]; 3]) {
+        for (&[x, y, z, w], e) in POINTS.iter().zip(expected.iter()) {
+            let v = [
+                noise.noise2(x, y),
+                noise.noise3(x, y, z),
+                noise.noise4(x, y, z, w),
+            ];
+            for (v, e) in v.iter().zip(e.iter()) {
+                assert!((v - e).abs() < 1e-12, "{} != {}", v, e);
+            }
+        }
+    }
+
+    // Samples `noise` in 2D, 3D and 4D over a few hundred units in each
+    // direction.
+    fn samples(noise: &dyn Noise) -> Vec<f64> {
+        (0..1000)
+            .flat_map(|i| {
+                let t = i as f64 * 0.613;
+                let (x, y, z, w) = (t, t * 0.618 + 3.1, 7.7 - t * 0.41, t * 0.29);
+                [
+                    noise.noise2(x, y),
+                    noise.noise3(x, y, z),
+                    noise.noise4(x, y, z, w),
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn perlin_golden() {
+        check(
+            &Perlin::seeded(1),
+            [
+                [-0.34912109375, 0.30138158798217773, -0.006688180865478504],
+                [
+                    -0.27580877183999974,
+                    -0.4729588141074421,
+                    0.16256968853813708,
+                ],
+                [
+                    -0.38840876194500035,
+                    0.21359764422500263,
+                    -0.09152190124909025,
+                ],
+            ],
+        );
+    }
+
+    #[test]
+    fn value_golden() {
+        check(
+            &Value::seeded(1),
+            [
+                [
+                    -0.17516084558823528,
+                    0.16197396072686884,
+                    -0.5860120594458487,
+                ],
+                [
+                    -0.27972242728156854,
+                    -0.41085894146662383,
+                    -0.632115919927253,
+                ],
+                [
+                    0.6126153694419607,
+                    -0.7377592607672945,
+                    -0.24893518031847336,
+                ],
+            ],
+        );
+    }
+
+    #[test]
+    fn simplex_golden() {
+        check(
+            &Simplex::seeded(1),
+            [
+                [
+                    -0.39228467881662066,
+                    -0.06150937499999901,
+                    0.1771676930058639,
+                ],
+                [
+                    -0.06847348735148351,
+                    0.6567493337283938,
+                    -0.5195732579958113,
+                ],
+                [
+                    -0.3551996394932684,
+                    0.4339531703292131,
+                    -0.05557163685433764,
+                ],
+            ],
+        );
+    }
+
+    #[test]
+    fn opensimplex_golden() {
+        check(
+            &OpenSimplex::seeded(1),
+            [
+                [-0.7565533326456153, -0.366455078125, 0.05931650441175002],
+                [0.0622155024490044, 0.11772156518518563, -0.4407694600718636],
+                [
+                    0.7153783020088358,
+                    0.41902915327674606,
+                    -0.29884212659935777,
+                ],
+            ],
+        );
+    }
+
+    #[test]
+    fn opensimplex_continuous() {
+        // a corner that was left out of the sum where it is still in reach
+        // would show up as a jump between neighboring samples.
+        let noise = OpenSimplex::seeded(3);
+        for line in 0..20 {
+            let p = [0.37, -1.9, 4.1, 2.3].map(|v| v * line as f64);
+            let d = [0.8, 0.35, -0.41, 0.26];
+            let at = |t: f64| {
+                let [x, y, z, w] = [0, 1, 2, 3].map(|i| p[i] + d[i] * t);
+                [
+                    noise.noise2(x, y),
+                    noise.noise3(x, y, z),
+                    noise.noise4(x, y, z, w),
+                ]
+            };
+            let mut last = at(0.0);
+            for i in 1..20000 {
+                let v = at(i as f64 * 1e-3);
+                for (v, last) in v.iter().zip(last.iter()) {
+                    assert!((v - last).abs() < 0.01, "{} {}", last, v);
+                }
+                last = v;
+            }
+        }
+    }
+
+    #[test]
+    fn seeds_differ() {
+        let (a, b) = (Simplex::seeded(1), Simplex::seeded(2));
+        assert_ne!(samples(&a), samples(&b));
+        assert_eq!(samples(&a), samples(&Simplex::seeded(1)));
+        let (a, b) = (OpenSimplex::seeded(1), OpenSimplex::seeded(2));
+        assert_ne!(samples(&a), samples(&b));
+        assert_eq!(samples(&a), samples(&OpenSimplex::seeded(1)));
+    }
+
+    #[test]
+    fn ranges() {
+        let within = |v: &[f64], lo: f64, hi: f64| v.iter().all(|v| (lo..=hi).contains(v));
+        for seed in 0..2 {
+            let (perlin, value, simplex, opensimplex) = (
+                Perlin::seeded(seed),
+                Value::seeded(seed),
+                Simplex::seeded(seed),
+                OpenSimplex::seeded(seed),
+            );
+            for base in [&perlin as &dyn Noise, &value, &simplex, &opensimplex] {
+                assert!(within(&samples(base), -1.0, 1.0));
+                assert!(within(&samples(&Fractal::fbm(base, 5)), -1.0, 1.0));
+
+                let turbulence = samples(&Fractal::turbulence(base, 5));
+                assert!(within(&turbulence, 0.0, 1.0));
+                let ridged = samples(&Fractal::ridged(base, 5));
+                assert!(within(&ridged, 0.0, 1.0));
+                // both spread over much of their range rather than sitting at
+                // one end of it.
+                for v in [turbulence, ridged] {
+                    let (lo, hi) = v
+                        .iter()
+                        .fold((1.0_f64, 0.0_f64), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+                    assert!(hi - lo > 0.3, "{}..{}", lo, hi);
+                }
+            }
+        }
+    }
+}
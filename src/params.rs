@@ -0,0 +1,326 @@
+use crate::error::SketchError;
+use rand::{distributions::uniform::SampleUniform, Rng, RngCore};
+use std::{cell::RefCell, fmt::Display, ops::Range, str::FromStr};
+
+#[derive(Debug, Clone, Copy)]
+pub enum Kind {
+    /// An integer drawn uniformly from `lo..hi`.
+    Int(i64, i64),
+
+    /// A float drawn uniformly from `lo..hi`.
+    Float(f64, f64),
+
+    /// A float that is not random, but can still be overridden.
+    Fixed(f64),
+}
+
+/// The declaration of a tunable parameter of a sketch.
+#[derive(Debug, Clone, Copy)]
+pub struct Param {
+    name: &'static str,
+    kind: Kind,
+    min: Option<f64>,
+}
+
+impl Param {
+    pub const fn int(name: &'static str, range: Range<i64>) -> Self {
+        Self {
+            name,
+            kind: Kind::Int(range.start, range.end),
+            min: None,
+        }
+    }
+
+    pub const fn float(name: &'static str, range: Range<f64>) -> Self {
+        Self {
+            name,
+            kind: Kind::Float(range.start, range.end),
+            min: None,
+        }
+    }
+
+    pub const fn fixed(name: &'static str, v: f64) -> Self {
+        Self {
+            name,
+            kind: Kind::Fixed(v),
+            min: None,
+        }
+    }
+
+    /// The least value that an override may take, for params that the sketch
+    /// cannot draw with below some value.
+    pub const fn with_min(self, min: f64) -> Self {
+        Self {
+            min: Some(min),
+            ..self
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.min
+    }
+}
+
+impl std::fmt::Display for Param {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            Kind::Int(lo, hi) => write!(f, "{}: int in {}..{}", self.name, lo, hi),
+            Kind::Float(lo, hi) => write!(f, "{}: float in {}..{}", self.name, lo, hi),
+            Kind::Fixed(v) => write!(f, "{}: float = {}", self.name, v),
+        }?;
+        match self.min {
+            Some(min) => write!(f, ", at least {}", min),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Resolves the values of parameters during a render. Values are drawn from
+/// the render's RNG unless they were overridden, and every resolved value is
+/// recorded so that it can be reported back and pinned.
+#[derive(Debug, Default)]
+pub struct Params {
+    sketch: String,
+    overrides: Vec<(String, String)>,
+    resolved: RefCell<Vec<(String, String)>>,
+}
+
+impl Params {
+    pub fn new(sketch: &str, overrides: Vec<(String, String)>) -> Self {
+        Self {
+            sketch: sketch.to_owned(),
+            overrides,
+            resolved: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn parse_override(s: &str) -> Result<(String, String), String> {
+        match s.find('=') {
+            Some(ix) => Ok((s[..ix].to_owned(), s[ix + 1..].to_owned())),
+            None => Err(format!("invalid param: {}, expected name=value", s)),
+        }
+    }
+
+    pub fn overrides(&self) -> &[(String, String)] {
+        &self.overrides
+    }
+
+    /// Ensures that every override names one of the declared parameters, and
+    /// that its value parses and is no less than the param's minimum.
+    pub fn validate(&self, params: &[Param]) -> Result<(), SketchError> {
+        for (name, _) in self.overrides.iter() {
+            let Some(param) = params.iter().find(|p| p.name == name) else {
+                return Err(self.invalid(name, "the sketch has no such param"));
+            };
+            match param.kind {
+                Kind::Int(..) => self.int_override(param).map(|_| ())?,
+                Kind::Float(..) | Kind::Fixed(..) => self.float_override(param).map(|_| ())?,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn resolved(&self) -> Vec<(String, String)> {
+        self.resolved.borrow().clone()
+    }
+
+    fn invalid<S: Into<String>>(&self, name: &str, reason: S) -> SketchError {
+        SketchError::InvalidParam {
+            sketch: self.sketch.clone(),
+            param: name.to_owned(),
+            reason: reason.into(),
+        }
+    }
+
+    fn find_override(&self, name: &str) -> Option<&str> {
+        self.overrides
+            .iter()
+            .rev()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn record<T: Display>(&self, param: &Param, v: T) -> T {
+        self.resolved
+            .borrow_mut()
+            .push((param.name.to_owned(), format!("{}", v)));
+        v
+    }
+
+    fn parse_override_as<T: FromStr>(&self, param: &Param) -> Result<Option<T>, SketchError> {
+        self.find_override(param.name)
+            .map(|v| {
+                v.parse::<T>()
+                    .map_err(|_| self.invalid(param.name, format!("cannot parse {:?}", v)))
+            })
+            .transpose()
+    }
+
+    fn check_min(&self, param: &Param, v: f64) -> Result<(), SketchError> {
+        match param.min {
+            Some(min) if v < min || v.is_nan() => {
+                Err(self.invalid(param.name, format!("must be at least {}", min)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn int_override(&self, param: &Param) -> Result<Option<i64>, SketchError> {
+        let v = self.parse_override_as::<i64>(param)?;
+        if let Some(v) = v {
+            self.check_min(param, v as f64)?;
+        }
+        Ok(v)
+    }
+
+    fn float_override(&self, param: &Param) -> Result<Option<f64>, SketchError> {
+        let v = self.parse_override_as::<f64>(param)?;
+        if let Some(v) = v {
+            self.check_min(param, v)?;
+        }
+        Ok(v)
+    }
+
+    /// Resolves an int parameter. The value is sampled in the type `T` so that
+    /// the RNG stream is the same as calling `gen_range` on that type, and it is
+    /// sampled even when overridden so later values do not shift.
+    pub fn int<T>(&self, rng: &mut dyn RngCore, param: &Param) -> Result<T, SketchError>
+    where
+        T: SampleUniform + TryFrom<i64> + FromStr + Display + PartialOrd,
+    {
+        let (lo, hi) = match param.kind {
+            Kind::Int(lo, hi) => (lo, hi),
+            _ => return Err(self.invalid(param.name, "not an int param")),
+        };
+        let bound = |v: i64| {
+            T::try_from(v).map_err(|_| self.invalid(param.name, format!("{} is out of range", v)))
+        };
+        let v = rng.gen_range(bound(lo)?..bound(hi)?);
+        let v = match self.int_override(param)? {
+            Some(o) => bound(o)?,
+            None => v,
+        };
+        Ok(self.record(param, v))
+    }
+
+    /// Resolves a float parameter. As with `int`, random values are sampled even
+    /// when overridden.
+    pub fn float(&self, rng: &mut dyn RngCore, param: &Param) -> Result<f64, SketchError> {
+        let v = match param.kind {
+            Kind::Float(lo, hi) => rng.gen_range(lo..hi),
+            Kind::Fixed(v) => v,
+            Kind::Int(..) => return Err(self.invalid(param.name, "not a float param")),
+        };
+        let v = self.float_override(param)?.unwrap_or(v);
+        Ok(self.record(param, v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    const N: Param = Param::int("n", 2..10).with_min(1.0);
+    const X: Param = Param::float("x", 0.5..1.5).with_min(0.0);
+    const W: Param = Param::fixed("w", 2.5);
+
+    fn params(overrides: &[&str]) -> Params {
+        let overrides = overrides
+            .iter()
+            .map(|s| Params::parse_override(s).unwrap())
+            .collect();
+        Params::new("t", overrides)
+    }
+
+    fn reason(e: SketchError) -> String {
+        match e {
+            SketchError::InvalidParam { param, reason, .. } => format!("{}: {}", param, reason),
+            e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn parse_override() {
+        assert_eq!(
+            Params::parse_override("n=3"),
+            Ok(("n".to_owned(), "3".to_owned()))
+        );
+        assert_eq!(
+            Params::parse_override("expr=a=b"),
+            Ok(("expr".to_owned(), "a=b".to_owned()))
+        );
+        assert_eq!(
+            Params::parse_override("n="),
+            Ok(("n".to_owned(), String::new()))
+        );
+        assert!(Params::parse_override("n").is_err());
+        assert!(Params::parse_override("").is_err());
+    }
+
+    #[test]
+    fn validate() {
+        let all = [N, X, W];
+        assert!(params(&["n=4", "x=0", "w=-1"]).validate(&all).is_ok());
+        let err = |o| reason(params(&[o]).validate(&all).unwrap_err());
+        assert_eq!(err("m=1"), "m: the sketch has no such param");
+        assert_eq!(err("n=0"), "n: must be at least 1");
+        assert_eq!(err("n=1.5"), "n: cannot parse \"1.5\"");
+        assert_eq!(err("n="), "n: cannot parse \"\"");
+        assert_eq!(err("x=-0.1"), "x: must be at least 0");
+        assert_eq!(err("x=NaN"), "x: must be at least 0");
+        assert_eq!(err("x=big"), "x: cannot parse \"big\"");
+    }
+
+    #[test]
+    fn kinds() {
+        let mut rng = Pcg64::seed_from_u64(1);
+        let p = params(&["n=42", "x=7.25", "w=3"]);
+        assert_eq!(p.int::<usize>(&mut rng, &N).unwrap(), 42);
+        assert_eq!(p.float(&mut rng, &X).unwrap(), 7.25);
+        assert_eq!(p.float(&mut rng, &W).unwrap(), 3.0);
+        assert!(p.float(&mut rng, &N).is_err());
+        assert!(p.int::<i32>(&mut rng, &X).is_err());
+        assert!(params(&["n=-1"]).int::<usize>(&mut rng, &N).is_err());
+
+        let p = params(&[]);
+        assert!((2..10).contains(&p.int::<i32>(&mut rng, &N).unwrap()));
+        assert!((0.5..1.5).contains(&p.float(&mut rng, &X).unwrap()));
+        assert_eq!(p.float(&mut rng, &W).unwrap(), 2.5);
+
+        // the last override of a param wins.
+        let p = params(&["n=3", "n=5"]);
+        assert_eq!(p.int::<u8>(&mut rng, &N).unwrap(), 5);
+        assert_eq!(p.resolved(), vec![("n".to_owned(), "5".to_owned())]);
+    }
+
+    #[test]
+    fn overrides_keep_rng_stream() {
+        let draw = |p: &Params| {
+            let mut rng = Pcg64::seed_from_u64(7);
+            let n = p.int::<u32>(&mut rng, &N).unwrap();
+            let x = p.float(&mut rng, &X).unwrap();
+            let w = p.float(&mut rng, &W).unwrap();
+            (n, x, w, rng.gen::<u64>())
+        };
+        let free = draw(&params(&[]));
+        let pinned = draw(&params(&["n=9", "x=0.25", "w=1"]));
+        assert_eq!(pinned.0, 9);
+        assert_eq!(pinned.1, 0.25);
+        assert_eq!(pinned.2, 1.0);
+        assert_eq!(free.3, pinned.3);
+
+        let pin_n = draw(&params(&["n=9"]));
+        assert_eq!(free.1, pin_n.1);
+        assert_eq!(free.3, pin_n.3);
+    }
+}
@@ -0,0 +1,462 @@
+use crate::{Point, Rect};
+use cairo::Context;
+
+// The number of lines each curve is split into when measuring a path.
+const CURVE_STEPS: usize = 32;
+
+/// How a `Path2` gets from one point to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Smoothing {
+    /// Straight lines between the points.
+    #[default]
+    Polyline,
+    /// Eases across to the next point, but when the step up or down is more
+    /// than the distance across, rounds the corners into a quarter circle on
+    /// each side of a straight vertical run. The points should run left to
+    /// right.
+    RoundedStep,
+    /// Eases vertically between points, with straight lines between points
+    /// that are directly above one another.
+    VerticalEase,
+    /// A Catmull-Rom spline, which passes through every point.
+    CatmullRom,
+    /// Chaikin's corner cutting, applied this many times. The ends stay where
+    /// they are and the curve pulls away from the points between them.
+    Chaikin(usize),
+}
+
+/// How the ends of a `Path2` are joined up when it is drawn as a closed shape.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Close {
+    /// A straight line from the end back to the start.
+    Straight,
+    /// Straight down, or up, from each end to the horizontal line at `y`, such
+    /// as the top or bottom edge of an image.
+    ToY(f64),
+    /// Straight across from each end to the vertical line at `x`.
+    ToX(f64),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Segment {
+    Line(Point),
+    Curve(Point, Point, Point),
+}
+
+/// A path through a list of points that can be smoothed, added to a cairo
+/// path, and measured.
+#[derive(Debug, Clone, Default)]
+pub struct Path2 {
+    pts: Vec<Point>,
+    smoothing: Smoothing,
+    ends: Option<(Point, Point)>,
+}
+
+impl Path2 {
+    pub fn new(pts: Vec<Point>) -> Self {
+        Self {
+            pts,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_smoothing(self, smoothing: Smoothing) -> Self {
+        Self { smoothing, ..self }
+    }
+
+    /// Runs straight, unsmoothed lines from `start` to the first point and
+    /// from the last point to `end`, such as out to the edges of an image.
+    pub fn with_ends(self, start: Point, end: Point) -> Self {
+        Self {
+            ends: Some((start, end)),
+            ..self
+        }
+    }
+
+    pub fn push(&mut self, p: Point) {
+        self.pts.push(p);
+    }
+
+    pub fn points(&self) -> &[Point] {
+        &self.pts
+    }
+
+    pub fn first(&self) -> Option<&Point> {
+        self.pts.first()
+    }
+
+    pub fn last(&self) -> Option<&Point> {
+        self.pts.last()
+    }
+
+    pub fn smoothing(&self) -> Smoothing {
+        self.smoothing
+    }
+
+    // Where the path starts, including its ends, and the segments from there.
+    fn segments(&self) -> Option<(Point, Vec<Segment>)> {
+        let &first = self.pts.first()?;
+        let mut segs = Vec::new();
+        let start = match self.ends {
+            Some((start, _)) => {
+                segs.push(Segment::Line(first));
+                start
+            }
+            None => first,
+        };
+        match self.smoothing {
+            Smoothing::Polyline => segs.extend(self.pts[1..].iter().map(|&p| Segment::Line(p))),
+            Smoothing::RoundedStep => rounded_step(&self.pts, &mut segs),
+            Smoothing::VerticalEase => vertical_ease(&self.pts, &mut segs),
+            Smoothing::CatmullRom => catmull_rom(&self.pts, &mut segs),
+            Smoothing::Chaikin(n) => {
+                segs.extend(chaikin(&self.pts, n)[1..].iter().map(|&p| Segment::Line(p)))
+            }
+        }
+        if let Some((_, end)) = self.ends {
+            segs.push(Segment::Line(end));
+        }
+        Some((start, segs))
+    }
+
+    /// Adds the path to the current path, with a line from the current point
+    /// to the start of the path if there is one.
+    pub fn trace(&self, ctx: &Context) {
+        if let Some((start, segs)) = self.segments() {
+            ctx.line_to(start.x(), start.y());
+            emit(ctx, &segs);
+        }
+    }
+
+    /// Adds the path to the current path as a new sub-path.
+    pub fn draw(&self, ctx: &Context) {
+        if let Some((start, segs)) = self.segments() {
+            ctx.move_to(start.x(), start.y());
+            emit(ctx, &segs);
+        }
+    }
+
+    /// Adds the path to the current path as a closed sub-path, for filling.
+    pub fn draw_closed(&self, ctx: &Context, close: Close) {
+        let Some((start, segs)) = self.segments() else {
+            return;
+        };
+        let end = end_of(&segs).unwrap_or(start);
+        match close {
+            Close::Straight => ctx.move_to(start.x(), start.y()),
+            Close::ToY(y) => {
+                ctx.move_to(start.x(), y);
+                ctx.line_to(start.x(), start.y());
+            }
+            Close::ToX(x) => {
+                ctx.move_to(x, start.y());
+                ctx.line_to(start.x(), start.y());
+            }
+        }
+        emit(ctx, &segs);
+        match close {
+            Close::Straight => {}
+            Close::ToY(y) => ctx.line_to(end.x(), y),
+            Close::ToX(x) => ctx.line_to(x, end.y()),
+        }
+        ctx.close_path();
+    }
+
+    /// The path, with its curves split into lines.
+    pub fn flatten(&self) -> Vec<Point> {
+        let Some((start, segs)) = self.segments() else {
+            return Vec::new();
+        };
+        let mut pts = vec![start];
+        for seg in segs {
+            match seg {
+                Segment::Line(p) => pts.push(p),
+                Segment::Curve(c1, c2, p) => {
+                    let p0 = *pts.last().unwrap();
+                    pts.extend(
+                        (1..=CURVE_STEPS)
+                            .map(|i| cubic(p0, c1, c2, p, i as f64 / CURVE_STEPS as f64)),
+                    );
+                }
+            }
+        }
+        pts
+    }
+
+    /// The length of the path, measured along its flattened curves.
+    pub fn length(&self) -> f64 {
+        self.flatten()
+            .windows(2)
+            .map(|w| w[0].distance(&w[1]))
+            .sum()
+    }
+
+    /// The bounding box of the flattened path, or `None` if it has no points.
+    pub fn bounds(&self) -> Option<Rect> {
+        let pts = self.flatten();
+        let first = pts.first()?;
+        Some(
+            pts.iter()
+                .fold(Rect::new(first, first), |r, p| r.union(&Rect::new(p, p))),
+        )
+    }
+
+    /// The point `d` along the path, clamped to its ends, or `None` if it has
+    /// no points.
+    pub fn point_at(&self, d: f64) -> Option<Point> {
+        point_along(&self.flatten(), d)
+    }
+
+    /// `n` points evenly spaced along the path from one end to the other.
+    pub fn sample(&self, n: usize) -> Vec<Point> {
+        let pts = self.flatten();
+        let len = pts.windows(2).map(|w| w[0].distance(&w[1])).sum::<f64>();
+        let step = if n > 1 { len / (n - 1) as f64 } else { 0.0 };
+        (0..n)
+            .filter_map(|i| point_along(&pts, step * i as f64))
+            .collect()
+    }
+}
+
+impl From<Vec<Point>> for Path2 {
+    fn from(pts: Vec<Point>) -> Self {
+        Self::new(pts)
+    }
+}
+
+impl FromIterator<Point> for Path2 {
+    fn from_iter<I: IntoIterator<Item = Point>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+fn emit(ctx: &Context, segs: &[Segment]) {
+    for seg in segs {
+        match *seg {
+            Segment::Line(p) => ctx.line_to(p.x(), p.y()),
+            Segment::Curve(c1, c2, p) => ctx.curve_to(c1.x(), c1.y(), c2.x(), c2.y(), p.x(), p.y()),
+        }
+    }
+}
+
+fn end_of(segs: &[Segment]) -> Option<Point> {
+    segs.last().map(|seg| match *seg {
+        Segment::Line(p) => p,
+        Segment::Curve(_, _, p) => p,
+    })
+}
+
+fn cubic(p0: Point, c1: Point, c2: Point, p1: Point, t: f64) -> Point {
+    let s = 1.0 - t;
+    p0 * (s * s * s) + c1 * (3.0 * s * s * t) + c2 * (3.0 * s * t * t) + p1 * (t * t * t)
+}
+
+fn point_along(pts: &[Point], d: f64) -> Option<Point> {
+    let mut left = d.max(0.0);
+    for w in pts.windows(2) {
+        let len = w[0].distance(&w[1]);
+        if left <= len && len > 0.0 {
+            return Some(w[0].lerp(&w[1], left / len));
+        }
+        left -= len;
+    }
+    pts.last().copied()
+}
+
+fn rounded_step(pts: &[Point], segs: &mut Vec<Segment>) {
+    let p = Point::new;
+    for w in pts.windows(2) {
+        let (xa, ya) = (w[0].x(), w[0].y());
+        let (xb, yb) = (w[1].x(), w[1].y());
+        let dx = xb - xa;
+
+        let ow = dx / 2.0;
+        // the distance to the control points of a bezier quarter circle.
+        let c = ow * 0.5523;
+        if yb - ya > dx {
+            segs.push(Segment::Curve(
+                p(xa + c, ya),
+                p(xa + ow, ya + ow - c),
+                p(xa + ow, ya + ow),
+            ));
+            segs.push(Segment::Line(p(xb - ow, yb - ow)));
+            segs.push(Segment::Curve(
+                p(xb - ow, yb - ow + c),
+                p(xb - c, yb),
+                p(xb, yb),
+            ));
+        } else if ya - yb > dx {
+            segs.push(Segment::Curve(
+                p(xa + c, ya),
+                p(xa + ow, ya - ow + c),
+                p(xa + ow, ya - ow),
+            ));
+            segs.push(Segment::Line(p(xb - ow, yb + ow)));
+            segs.push(Segment::Curve(
+                p(xb - ow, yb + ow - c),
+                p(xb - c, yb),
+                p(xb, yb),
+            ));
+        } else {
+            segs.push(Segment::Curve(p(xa + ow, ya), p(xb - ow, yb), p(xb, yb)));
+        }
+    }
+}
+
+fn vertical_ease(pts: &[Point], segs: &mut Vec<Segment>) {
+    for w in pts.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        if (a.x() - b.x()).abs() < 0.001 {
+            segs.push(Segment::Line(b));
+        } else {
+            let cy = (b.y() - a.y()) / 2.0;
+            segs.push(Segment::Curve(
+                Point::new(a.x(), a.y() + cy),
+                Point::new(b.x(), a.y() + cy),
+                b,
+            ));
+        }
+    }
+}
+
+// A uniform Catmull-Rom spline as beziers, with the end points repeated so
+// that the curve reaches them.
+fn catmull_rom(pts: &[Point], segs: &mut Vec<Segment>) {
+    let n = pts.len();
+    for i in 1..n {
+        let p0 = pts[i.saturating_sub(2)];
+        let (p1, p2) = (pts[i - 1], pts[i]);
+        let p3 = pts[(i + 1).min(n - 1)];
+        segs.push(Segment::Curve(
+            p1 + (p2 - p0) / 6.0,
+            p2 - (p3 - p1) / 6.0,
+            p2,
+        ));
+    }
+}
+
+fn chaikin(pts: &[Point], iterations: usize) -> Vec<Point> {
+    let mut pts = pts.to_vec();
+    for _ in 0..iterations {
+        if pts.len() < 3 {
+            break;
+        }
+        let mut next = vec![pts[0]];
+        for w in pts.windows(2) {
+            next.push(w[0].lerp(&w[1], 0.25));
+            next.push(w[0].lerp(&w[1], 0.75));
+        }
+        next.push(*pts.last().unwrap());
+        // the cuts nearest the ends are dropped so that the ends don't get a
+        // short straight stub.
+        next.remove(1);
+        next.remove(next.len() - 2);
+        pts = next;
+    }
+    pts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Point, b: Point) -> bool {
+        a.distance(&b) < 1e-9
+    }
+
+    fn zigzag() -> Path2 {
+        Path2::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(3.0, 4.0),
+            Point::new(6.0, 0.0),
+        ])
+    }
+
+    #[test]
+    fn length() {
+        assert_eq!(zigzag().length(), 10.0);
+        assert_eq!(Path2::default().length(), 0.0);
+        assert_eq!(Path2::new(vec![Point::new(1.0, 1.0)]).length(), 0.0);
+        let ends = zigzag().with_ends(Point::new(-2.0, 0.0), Point::new(6.0, 5.0));
+        assert_eq!(ends.length(), 17.0);
+        // Chaikin cuts the corner, while Catmull-Rom still passes through it.
+        let len = zigzag().with_smoothing(Smoothing::Chaikin(3)).length();
+        assert!(len > 6.0 && len < 10.0, "{}", len);
+        assert!(zigzag().with_smoothing(Smoothing::CatmullRom).length() > 10.0);
+        let line = Path2::new(vec![Point::zero(), Point::new(4.0, 0.0)]);
+        let eased = line.with_smoothing(Smoothing::VerticalEase);
+        assert!((eased.length() - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bounds() {
+        assert_eq!(zigzag().bounds(), Some(Rect::from_ltrb(0.0, 0.0, 6.0, 4.0)));
+        assert_eq!(Path2::default().bounds(), None);
+        let p = Point::new(2.0, 3.0);
+        assert_eq!(Path2::new(vec![p]).bounds(), Some(Rect::new(&p, &p)));
+        // a Catmull-Rom spline passes through its points, so it reaches the top.
+        let b = zigzag()
+            .with_smoothing(Smoothing::CatmullRom)
+            .bounds()
+            .unwrap();
+        assert_eq!((b.left(), b.right()), (0.0, 6.0));
+        assert!(b.bottom() >= 4.0);
+    }
+
+    #[test]
+    fn point_at_ends() {
+        let path = zigzag();
+        assert_eq!(path.point_at(0.0), Some(Point::new(0.0, 0.0)));
+        assert_eq!(path.point_at(10.0), Some(Point::new(6.0, 0.0)));
+        assert_eq!(path.point_at(-1.0), Some(Point::new(0.0, 0.0)));
+        assert_eq!(path.point_at(100.0), Some(Point::new(6.0, 0.0)));
+        assert!(close(path.point_at(2.5).unwrap(), Point::new(1.5, 2.0)));
+        assert!(close(path.point_at(5.0).unwrap(), Point::new(3.0, 4.0)));
+        assert_eq!(Path2::default().point_at(0.0), None);
+    }
+
+    #[test]
+    fn sample() {
+        let pts = zigzag().sample(5);
+        assert_eq!(pts.len(), 5);
+        assert_eq!(pts[0], Point::new(0.0, 0.0));
+        assert!(close(pts[2], Point::new(3.0, 4.0)));
+        assert!(close(pts[4], Point::new(6.0, 0.0)));
+        for w in pts.windows(2) {
+            assert!((w[0].distance(&w[1]) - 2.5).abs() < 1e-9);
+        }
+        assert_eq!(zigzag().sample(1), vec![Point::new(0.0, 0.0)]);
+        assert!(zigzag().sample(0).is_empty());
+        assert!(Path2::default().sample(3).is_empty());
+    }
+
+    #[test]
+    fn chaikin_counts() {
+        // each pass doubles the segments, less the two cuts at the ends.
+        let mut n = 3;
+        for i in 0..5 {
+            let pts = chaikin(zigzag().points(), i);
+            assert_eq!(pts.len(), n, "{} iterations", i);
+            assert_eq!(pts[0], Point::new(0.0, 0.0));
+            assert_eq!(*pts.last().unwrap(), Point::new(6.0, 0.0));
+            n = 2 * n - 2;
+        }
+        // two points make a straight line, which has no corners to cut.
+        let line = [Point::zero(), Point::new(1.0, 1.0)];
+        assert_eq!(chaikin(&line, 4).len(), 2);
+        let flat = zigzag().with_smoothing(Smoothing::Chaikin(2)).flatten();
+        assert_eq!(flat, chaikin(zigzag().points(), 2));
+    }
+
+    #[test]
+    fn flatten_curves() {
+        let flat = zigzag().with_smoothing(Smoothing::CatmullRom).flatten();
+        assert_eq!(flat.len(), 1 + 2 * CURVE_STEPS);
+        assert_eq!(flat[CURVE_STEPS], Point::new(3.0, 4.0));
+        let flat = zigzag()
+            .with_ends(Point::new(-1.0, 0.0), Point::new(7.0, 0.0))
+            .with_smoothing(Smoothing::Polyline)
+            .flatten();
+        assert_eq!(flat.len(), 5);
+    }
+}
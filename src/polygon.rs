@@ -0,0 +1,569 @@
+use crate::{Point, Rect};
+use cairo::Context;
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::TAU;
+
+// Points are snapped to a grid this many to the unit, so that the pieces of
+// edges that meet at a crossing share an end exactly.
+const SNAP: f64 = 1e6;
+
+// The tolerance, relative to the lengths involved, for treating edges as
+// parallel or points as landing on the ends of edges.
+const EPS: f64 = 1e-9;
+
+// The largest angle covered by one line of a round join.
+const ROUND_STEP: f64 = TAU / 64.0;
+
+/// How the corners of a `Polygon` are filled in where offsetting opens a gap
+/// between the edges on either side.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Join {
+    /// Extends the edges until they meet, unless that is more than this many
+    /// times the offset from the corner, in which case the corner is beveled.
+    Miter(f64),
+    /// An arc around the corner.
+    Round,
+    /// A straight line across the gap.
+    Bevel,
+}
+
+/// A region of the plane bounded by one or more closed contours. Outer
+/// contours run clockwise and holes run counterclockwise, so that a point is
+/// inside when the contours wind around it a positive number of times.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Polygon {
+    contours: Vec<Vec<Point>>,
+}
+
+type Key = (i64, i64);
+
+type Edge = (Point, Point);
+
+impl Polygon {
+    /// The polygon inside `pts`, which may run either way and may cross
+    /// itself. Where it crosses itself, the regions alternate between inside
+    /// and outside.
+    pub fn new(pts: Vec<Point>) -> Self {
+        Self::from_contours(vec![pts])
+    }
+
+    /// The polygon inside `contours` by the even-odd rule, so that contours
+    /// nested in others are holes, whichever way they run.
+    pub fn from_contours(contours: Vec<Vec<Point>>) -> Self {
+        Self {
+            contours: resolve(&edges_of(&contours), |p| {
+                winding(&contours, p).rem_euclid(2) == 1
+            }),
+        }
+    }
+
+    pub fn rect(r: &Rect) -> Self {
+        Self {
+            contours: vec![vec![
+                *r.top_left(),
+                r.top_right(),
+                *r.bottom_right(),
+                r.bottom_left(),
+            ]],
+        }
+    }
+
+    /// A regular polygon with `n` corners on the circle of radius `r` around
+    /// `center`, which approaches a circle as `n` grows.
+    pub fn circle(center: &Point, r: f64, n: usize) -> Self {
+        if n < 3 || r <= 0.0 {
+            return Self::default();
+        }
+        Self {
+            contours: vec![(0..n)
+                .map(|i| *center + Point::from_polar(r, TAU * i as f64 / n as f64))
+                .collect()],
+        }
+    }
+
+    pub fn contours(&self) -> &[Vec<Point>] {
+        &self.contours
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contours.is_empty()
+    }
+
+    /// The area inside the polygon, with the holes taken out.
+    pub fn area(&self) -> f64 {
+        self.contours
+            .iter()
+            .flat_map(|c| c.iter().zip(c.iter().cycle().skip(1)))
+            .map(|(a, b)| a.cross(b) / 2.0)
+            .sum()
+    }
+
+    /// The bounding box of the polygon, or `None` if it is empty.
+    pub fn bounds(&self) -> Option<Rect> {
+        self.contours.iter().flatten().fold(None, |r, p| {
+            let pr = Rect::new(p, p);
+            Some(r.map_or(pr, |r: Rect| r.union(&pr)))
+        })
+    }
+
+    pub fn contains(&self, p: &Point) -> bool {
+        winding(&self.contours, p) > 0
+    }
+
+    pub fn union(&self, other: &Polygon) -> Polygon {
+        self.combine(other, |a, b| a || b)
+    }
+
+    pub fn intersection(&self, other: &Polygon) -> Polygon {
+        self.combine(other, |a, b| a && b)
+    }
+
+    /// The parts of this polygon that are outside of `other`.
+    pub fn difference(&self, other: &Polygon) -> Polygon {
+        self.combine(other, |a, b| a && !b)
+    }
+
+    /// The parts that are in one polygon or the other, but not both.
+    pub fn xor(&self, other: &Polygon) -> Polygon {
+        self.combine(other, |a, b| a != b)
+    }
+
+    fn combine(&self, other: &Polygon, op: impl Fn(bool, bool) -> bool) -> Polygon {
+        let mut edges = edges_of(&self.contours);
+        edges.extend(edges_of(&other.contours));
+        Polygon {
+            contours: resolve(&edges, |p| op(self.contains(p), other.contains(p))),
+        }
+    }
+
+    /// The polygon grown outward by `d`, or shrunk inward if `d` is negative,
+    /// with holes shrinking or growing to match. Parts that shrink away to
+    /// nothing are dropped, and parts that grow into one another are merged.
+    pub fn offset(&self, d: f64, join: Join) -> Polygon {
+        if d == 0.0 {
+            return self.clone();
+        }
+        let raw = self
+            .contours
+            .iter()
+            .map(|c| offset_contour(c, d, join))
+            .collect::<Vec<_>>();
+        Polygon {
+            contours: resolve(&edges_of(&raw), |p| winding(&raw, p) > 0),
+        }
+    }
+
+    /// Adds each contour to the current path as a closed sub-path, for filling
+    /// with the default winding fill rule, or stroking.
+    pub fn draw(&self, ctx: &Context) {
+        for c in &self.contours {
+            let Some(first) = c.first() else {
+                continue;
+            };
+            ctx.move_to(first.x(), first.y());
+            for p in &c[1..] {
+                ctx.line_to(p.x(), p.y());
+            }
+            ctx.close_path();
+        }
+    }
+}
+
+impl From<&Rect> for Polygon {
+    fn from(r: &Rect) -> Self {
+        Self::rect(r)
+    }
+}
+
+fn edges_of(contours: &[Vec<Point>]) -> Vec<Edge> {
+    contours
+        .iter()
+        .flat_map(|c| c.iter().zip(c.iter().cycle().skip(1)))
+        .map(|(&a, &b)| (a, b))
+        .filter(|(a, b)| a != b)
+        .collect()
+}
+
+// The number of times `contours` wind around `p`, counting clockwise as
+// positive.
+fn winding(contours: &[Vec<Point>], p: &Point) -> i32 {
+    let mut w = 0;
+    for c in contours {
+        for (a, b) in c.iter().zip(c.iter().cycle().skip(1)) {
+            let side = (*b - *a).cross(&(*p - *a));
+            if a.y() <= p.y() {
+                if b.y() > p.y() && side > 0.0 {
+                    w += 1;
+                }
+            } else if b.y() <= p.y() && side < 0.0 {
+                w -= 1;
+            }
+        }
+    }
+    w
+}
+
+// Where two edges cross or touch, as the fraction of the way along each and
+// the point. Overlapping parallel edges touch at the ends of the overlap.
+fn crossings(&(a, b): &Edge, &(c, d): &Edge) -> Vec<(f64, f64, Point)> {
+    let (r, s) = (b - a, d - c);
+    let (rl, sl) = (r.length(), s.length());
+    let denom = r.cross(&s);
+    let ac = c - a;
+    if denom.abs() > EPS * rl * sl {
+        let t = ac.cross(&s) / denom;
+        let u = ac.cross(&r) / denom;
+        let (te, ue) = (EPS * 10.0, EPS * 10.0);
+        if t < -te || t > 1.0 + te || u < -ue || u > 1.0 + ue {
+            return Vec::new();
+        }
+        // crossings at the ends of edges use the ends themselves.
+        let p = if t.abs() <= te {
+            a
+        } else if (t - 1.0).abs() <= te {
+            b
+        } else if u.abs() <= ue {
+            c
+        } else if (u - 1.0).abs() <= ue {
+            d
+        } else {
+            a + r * t
+        };
+        return vec![(t.clamp(0.0, 1.0), u.clamp(0.0, 1.0), p)];
+    }
+    // parallel, so they only meet if they lie along the same line.
+    if ac.cross(&r).abs() > EPS * rl * (rl + ac.length()) {
+        return Vec::new();
+    }
+    let along = |p: &Point, o: &Point, v: &Point| (*p - *o).dot(v) / v.length_squared();
+    let mut hits = Vec::new();
+    for p in [c, d] {
+        let t = along(&p, &a, &r);
+        if (0.0..=1.0).contains(&t) {
+            hits.push((t, along(&p, &c, &s), p));
+        }
+    }
+    for p in [a, b] {
+        let u = along(&p, &c, &s);
+        if (0.0..=1.0).contains(&u) {
+            hits.push((along(&p, &a, &r), u, p));
+        }
+    }
+    hits
+}
+
+fn key(p: &Point) -> Key {
+    ((p.x() * SNAP).round() as i64, (p.y() * SNAP).round() as i64)
+}
+
+// Splits `edges` wherever they cross, and joins the pieces that have the
+// inside on their right and the outside on their left into clockwise contours
+// around the inside, or the reverse for pieces that face the other way.
+// Pieces with the inside or the outside on both sides are dropped.
+fn resolve(edges: &[Edge], inside: impl Fn(&Point) -> bool) -> Vec<Vec<Point>> {
+    let mut splits = edges
+        .iter()
+        .map(|&(a, b)| vec![(0.0, a), (1.0, b)])
+        .collect::<Vec<_>>();
+    let boxes = edges
+        .iter()
+        .map(|(a, b)| Rect::new(a, a).union(&Rect::new(b, b)))
+        .collect::<Vec<_>>();
+    for i in 0..edges.len() {
+        for j in i + 1..edges.len() {
+            let (bi, bj) = (&boxes[i], &boxes[j]);
+            if bi.left() > bj.right()
+                || bj.left() > bi.right()
+                || bi.top() > bj.bottom()
+                || bj.top() > bi.bottom()
+            {
+                continue;
+            }
+            for (t, u, p) in crossings(&edges[i], &edges[j]) {
+                splits[i].push((t, p));
+                splits[j].push((u, p));
+            }
+        }
+    }
+
+    let scale = boxes
+        .iter()
+        .fold(0.0_f64, |m, b| m.max(b.width()).max(b.height()));
+    let mut pts: HashMap<Key, Point> = HashMap::new();
+    let mut seen = HashSet::new();
+    let mut pieces = Vec::new();
+    for mut split in splits {
+        split.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let keys = split
+            .iter()
+            .map(|(_, p)| {
+                let k = key(p);
+                pts.entry(k).or_insert(*p);
+                k
+            })
+            .collect::<Vec<_>>();
+        for w in keys.windows(2) {
+            let (ka, kb) = (w[0], w[1]);
+            // pieces that lie along the same line are only kept once.
+            if ka != kb && seen.insert((ka.min(kb), ka.max(kb))) {
+                let (a, b) = (pts[&ka], pts[&kb]);
+                let d = b - a;
+                let side = d.perp().normalize() * (d.length() * 1e-3).min(scale * 1e-7);
+                let mid = a.lerp(&b, 0.5);
+                match (inside(&(mid + side)), inside(&(mid - side))) {
+                    (true, false) => pieces.push((ka, kb)),
+                    (false, true) => pieces.push((kb, ka)),
+                    _ => {}
+                }
+            }
+        }
+    }
+    chain(&pieces, &pts)
+}
+
+// Joins pieces end to end into closed contours. Where there is a choice of
+// piece to go on with, the one that turns farthest toward the inside is taken,
+// so that regions that touch at a corner come out as separate contours. As
+// many pieces leave each point as arrive at it, so every chain should close,
+// but one that runs into a dead end, where snapping has merged or split
+// points unevenly, bounds nothing and is dropped.
+fn chain(pieces: &[(Key, Key)], pts: &HashMap<Key, Point>) -> Vec<Vec<Point>> {
+    let mut from: HashMap<Key, Vec<usize>> = HashMap::new();
+    for (i, &(a, _)) in pieces.iter().enumerate() {
+        from.entry(a).or_default().push(i);
+    }
+    let mut used = vec![false; pieces.len()];
+    let mut contours = Vec::new();
+    for start in 0..pieces.len() {
+        if used[start] {
+            continue;
+        }
+        let mut contour = Vec::new();
+        let mut cur = start;
+        let closed = loop {
+            used[cur] = true;
+            let (a, b) = pieces[cur];
+            contour.push(pts[&a]);
+            if b == pieces[start].0 {
+                break true;
+            }
+            let dir = pts[&b] - pts[&a];
+            let next = from.get(&b).and_then(|ns| {
+                ns.iter().copied().filter(|&n| !used[n]).max_by(|&m, &n| {
+                    let turn = |i: usize| {
+                        let d = pts[&pieces[i].1] - pts[&b];
+                        dir.cross(&d).atan2(dir.dot(&d))
+                    };
+                    turn(m).total_cmp(&turn(n))
+                })
+            });
+            match next {
+                Some(n) => cur = n,
+                None => break false,
+            }
+        };
+        if closed {
+            let contour = simplify(contour);
+            if contour.len() >= 3 {
+                contours.push(contour);
+            }
+        }
+    }
+    contours
+}
+
+// Drops the corners of a contour that lie on a straight line between their
+// neighbors.
+fn simplify(mut pts: Vec<Point>) -> Vec<Point> {
+    let mut i = 0;
+    while pts.len() >= 3 && i < pts.len() {
+        let n = pts.len();
+        let (a, b, c) = (pts[(i + n - 1) % n], pts[i], pts[(i + 1) % n]);
+        let (u, v) = (b - a, c - b);
+        if u.cross(&v).abs() <= EPS * u.length() * v.length() && u.dot(&v) > 0.0 {
+            pts.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    pts
+}
+
+// A contour with each edge moved out by `d` and the corners joined up. Where
+// the edges overlap instead, they are joined through the original corner, and
+// the loops that makes are removed when the result is resolved.
+fn offset_contour(c: &[Point], d: f64, join: Join) -> Vec<Point> {
+    let n = c.len();
+    let mut out = Vec::new();
+    for i in 0..n {
+        let (prev, v, next) = (c[(i + n - 1) % n], c[i], c[(i + 1) % n]);
+        let (d0, d1) = ((v - prev).normalize(), (next - v).normalize());
+        let (n0, n1) = (-d0.perp() * d, -d1.perp() * d);
+        let (p0, p1) = (v + n0, v + n1);
+        let turn = d0.cross(&d1);
+        if turn.abs() <= EPS && d0.dot(&d1) > 0.0 {
+            out.push(p0);
+        } else if turn * d > 0.0 || turn.abs() <= EPS {
+            match join {
+                Join::Bevel => out.extend([p0, p1]),
+                Join::Miter(limit) => {
+                    let cos_half = ((1.0 + d0.dot(&d1)) / 2.0).sqrt();
+                    if cos_half * limit > 1.0 {
+                        out.push(v + (n0 + n1).normalize() * (d.abs() / cos_half));
+                    } else {
+                        out.extend([p0, p1]);
+                    }
+                }
+                Join::Round => {
+                    let sweep = n0.cross(&n1).atan2(n0.dot(&n1));
+                    let sweep = if turn.abs() <= EPS {
+                        sweep.abs() * d.signum()
+                    } else {
+                        sweep
+                    };
+                    let steps = (sweep.abs() / ROUND_STEP).ceil().max(1.0) as usize;
+                    let a0 = n0.angle();
+                    out.extend((0..=steps).map(|k| {
+                        v + Point::from_polar(d.abs(), a0 + sweep * k as f64 / steps as f64)
+                    }));
+                }
+            }
+        } else {
+            out.extend([p0, v, p1]);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(l: f64, t: f64, r: f64, b: f64) -> Polygon {
+        Polygon::rect(&Rect::from_ltrb(l, t, r, b))
+    }
+
+    fn assert_area(p: &Polygon, area: f64) {
+        assert!((p.area() - area).abs() < 1e-6, "{} != {}", p.area(), area);
+    }
+
+    // An L with arms 40 wide, clockwise on screen.
+    fn ell() -> Polygon {
+        Polygon::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(100.0, 0.0),
+            Point::new(100.0, 40.0),
+            Point::new(40.0, 40.0),
+            Point::new(40.0, 100.0),
+            Point::new(0.0, 100.0),
+        ])
+    }
+
+    #[test]
+    fn boolean_ops() {
+        let a = rect(0.0, 0.0, 100.0, 100.0);
+        // the areas of the union, intersection, difference and xor, and the
+        // number of contours in the union.
+        let cases = [
+            (
+                rect(50.0, 50.0, 150.0, 150.0),
+                [17500.0, 2500.0, 7500.0, 15000.0],
+                1,
+            ),
+            (
+                rect(100.0, 0.0, 200.0, 100.0),
+                [20000.0, 0.0, 10000.0, 20000.0],
+                1,
+            ),
+            (
+                rect(25.0, 25.0, 75.0, 75.0),
+                [10000.0, 2500.0, 7500.0, 7500.0],
+                1,
+            ),
+            (
+                rect(200.0, 0.0, 300.0, 100.0),
+                [20000.0, 0.0, 10000.0, 20000.0],
+                2,
+            ),
+        ];
+        for (b, [union, intersection, difference, xor], contours) in cases {
+            assert_area(&a.union(&b), union);
+            assert_area(&a.intersection(&b), intersection);
+            assert_area(&a.difference(&b), difference);
+            assert_area(&a.xor(&b), xor);
+            assert_eq!(a.union(&b).contours().len(), contours);
+            assert_eq!(a.intersection(&b).is_empty(), intersection == 0.0);
+        }
+
+        // taking out the middle leaves a hole.
+        let ring = a.difference(&rect(25.0, 25.0, 75.0, 75.0));
+        assert_eq!(ring.contours().len(), 2);
+        assert!(!ring.contains(&Point::new(50.0, 50.0)));
+        assert!(ring.contains(&Point::new(10.0, 50.0)));
+    }
+
+    #[test]
+    fn holes() {
+        let square = |l: f64, r: f64| {
+            vec![
+                Point::new(l, l),
+                Point::new(r, l),
+                Point::new(r, r),
+                Point::new(l, r),
+            ]
+        };
+        let ring = Polygon::from_contours(vec![square(0.0, 100.0), square(25.0, 75.0)]);
+        assert_area(&ring, 7500.0);
+        assert!(ring.contains(&Point::new(10.0, 10.0)));
+        assert!(!ring.contains(&Point::new(50.0, 50.0)));
+        assert!(!ring.contains(&Point::new(150.0, 50.0)));
+        let bounds = ring.bounds().unwrap();
+        assert_eq!((bounds.width(), bounds.height()), (100.0, 100.0));
+
+        // an island in the hole, and a window across the hole.
+        assert_area(&ring.union(&rect(40.0, 40.0, 60.0, 60.0)), 7900.0);
+        assert_area(&ring.intersection(&rect(20.0, 20.0, 80.0, 80.0)), 1100.0);
+        assert_area(&ring.union(&rect(20.0, 20.0, 80.0, 80.0)), 10000.0);
+        // the hole shrinks as the ring grows.
+        assert_area(
+            &ring.offset(5.0, Join::Miter(4.0)),
+            110.0 * 110.0 - 40.0 * 40.0,
+        );
+        assert!(ring.offset(-15.0, Join::Miter(4.0)).is_empty());
+    }
+
+    #[test]
+    fn offset_joins() {
+        let l = ell();
+        assert_area(&l, 6400.0);
+        let round_loss = 100.0 - 25.0 * std::f64::consts::PI;
+
+        // growing the L rounds or cuts off its five outer corners, and fills
+        // in the inner one.
+        assert_area(&l.offset(10.0, Join::Miter(4.0)), 10800.0);
+        let round = l.offset(10.0, Join::Round).area();
+        assert!(
+            (round - (10800.0 - 5.0 * round_loss)).abs() < 1.0,
+            "{}",
+            round
+        );
+        assert_area(&l.offset(10.0, Join::Bevel), 10800.0 - 5.0 * 50.0);
+        // a miter limit below sqrt(2) bevels the square corners.
+        assert_area(&l.offset(10.0, Join::Miter(1.2)), 10800.0 - 5.0 * 50.0);
+
+        // shrinking it leaves an L with arms 20 wide. Only its inner corner
+        // opens up, and a round or bevel join there reaches past the miter.
+        assert_area(&l.offset(-10.0, Join::Miter(4.0)), 2800.0);
+        let round = l.offset(-10.0, Join::Round).area();
+        assert!((round - (2800.0 + round_loss)).abs() < 1.0, "{}", round);
+        assert_area(&l.offset(-10.0, Join::Bevel), 2800.0 + 50.0);
+
+        // the bevel across the inner corner is the last to go, at 80 / 3.
+        for join in [Join::Miter(4.0), Join::Round, Join::Bevel] {
+            assert!(l.offset(-30.0, join).is_empty());
+            assert!(l.offset(-60.0, join).is_empty());
+            assert!(!l.offset(-19.5, join).is_empty());
+            assert_eq!(l.offset(10.0, join).contours().len(), 1);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use crate::{grid::Grid, Point, Rect};
+use rand::{Rng, RngCore};
+use std::f64::consts::TAU;
+
+/// Bridson's Poisson-disk sampling, which fills a rect with points that are
+/// no closer than a radius to one another, but otherwise evenly random.
+#[derive(Debug, Clone, Copy)]
+pub struct PoissonDisk {
+    bounds: Rect,
+    radius: f64,
+    attempts: usize,
+}
+
+impl PoissonDisk {
+    pub fn new(bounds: Rect, radius: f64) -> Self {
+        Self {
+            bounds,
+            radius,
+            attempts: 30,
+        }
+    }
+
+    /// The number of candidates tried around each point before giving up on
+    /// it. More fill the rect more tightly, but take longer.
+    pub fn with_attempts(self, attempts: usize) -> Self {
+        Self { attempts, ..self }
+    }
+
+    pub fn bounds(&self) -> &Rect {
+        &self.bounds
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// Points that are all at least the radius apart.
+    pub fn sample(&self, rng: &mut dyn RngCore) -> Vec<Point> {
+        let r = self.radius;
+        self.sample_by(rng, r, |_| r)
+    }
+
+    /// Points whose spacing varies with `density`, which should give values
+    /// from 0 to 1 within the rect. The points are the radius apart where the
+    /// density is 1, and `max_radius` apart where it is 0. A `max_radius` that
+    /// is less than the radius, or NaN, is taken to be the radius.
+    pub fn sample_with_density(
+        &self,
+        rng: &mut dyn RngCore,
+        max_radius: f64,
+        density: impl Fn(&Point) -> f64,
+    ) -> Vec<Point> {
+        let (lo, hi) = (self.radius, max_radius.max(self.radius));
+        self.sample_by(rng, hi, |p| hi - (hi - lo) * density(p).clamp(0.0, 1.0))
+    }
+
+    // Samples with a radius at each point that is between the radius and
+    // `max_radius`. A candidate is kept when it is outside the radius at its
+    // own position from all of the points so far.
+    fn sample_by(
+        &self,
+        rng: &mut dyn RngCore,
+        max_radius: f64,
+        radius: impl Fn(&Point) -> f64,
+    ) -> Vec<Point> {
+        let b = &self.bounds;
+        if !self.radius.is_finite() || self.radius <= 0.0 || b.width() <= 0.0 || b.height() <= 0.0 {
+            return Vec::new();
+        }
+
+        // cells are small enough that no two points can share one.
+        let size = self.radius / 2.0_f64.sqrt();
+        let nx = (b.width() / size).ceil() as usize;
+        let ny = (b.height() / size).ceil() as usize;
+        let reach = (max_radius / size).ceil() as i64;
+        let mut cells: Vec<Option<usize>> = vec![None; nx * ny];
+        let cell_of = |p: &Point| {
+            let i = (((p.x() - b.left()) / size) as usize).min(nx - 1);
+            let j = (((p.y() - b.top()) / size) as usize).min(ny - 1);
+            (i, j)
+        };
+
+        let mut pts = Vec::new();
+        let mut active = Vec::new();
+        let first = Point::new(
+            rng.gen_range(b.left()..b.right()),
+            rng.gen_range(b.top()..b.bottom()),
+        );
+        let (i, j) = cell_of(&first);
+        cells[j * nx + i] = Some(0);
+        pts.push(first);
+        active.push(0);
+
+        while !active.is_empty() {
+            let k = rng.gen_range(0..active.len());
+            let p = pts[active[k]];
+            let r = radius(&p);
+            let found = (0..self.attempts).find_map(|_| {
+                // uniform over the area of the ring from r to 2r.
+                let d = r * rng.gen_range(1.0..4.0_f64).sqrt();
+                let q = p + Point::from_polar(d, rng.gen_range(0.0..TAU));
+                if !b.contains(&q) {
+                    return None;
+                }
+                let rq = radius(&q);
+                let (qi, qj) = cell_of(&q);
+                let (qi, qj) = (qi as i64, qj as i64);
+                let clear = (qj - reach..=qj + reach)
+                    .flat_map(|j| (qi - reach..=qi + reach).map(move |i| (i, j)))
+                    .filter(|&(i, j)| i >= 0 && j >= 0 && (i as usize) < nx && (j as usize) < ny)
+                    .filter_map(|(i, j)| cells[j as usize * nx + i as usize])
+                    .all(|n| pts[n].distance(&q) >= rq);
+                clear.then_some(q)
+            });
+            match found {
+                Some(q) => {
+                    let (i, j) = cell_of(&q);
+                    cells[j * nx + i] = Some(pts.len());
+                    active.push(pts.len());
+                    pts.push(q);
+                }
+                None => {
+                    active.swap_remove(k);
+                }
+            }
+        }
+        pts
+    }
+}
+
+/// Mitchell's best-candidate sampling, which places `n` points in `bounds`
+/// one at a time, each at whichever of `candidates` random spots is farthest
+/// from the points so far. Unlike `PoissonDisk` it gives exactly `n` points,
+/// and the points come out in an order where every prefix is well spread.
+pub fn best_candidate(
+    rng: &mut dyn RngCore,
+    bounds: &Rect,
+    n: usize,
+    candidates: usize,
+) -> Vec<Point> {
+    let mut pts: Vec<Point> = Vec::with_capacity(n);
+    if bounds.width() <= 0.0 || bounds.height() <= 0.0 {
+        return pts;
+    }
+    for _ in 0..n {
+        let mut best = None;
+        let mut best_d = -1.0;
+        for _ in 0..candidates.max(1) {
+            let q = Point::new(
+                rng.gen_range(bounds.left()..bounds.right()),
+                rng.gen_range(bounds.top()..bounds.bottom()),
+            );
+            let d = pts
+                .iter()
+                .map(|p| p.distance(&q))
+                .fold(f64::INFINITY, f64::min);
+            if d > best_d {
+                best = Some(q);
+                best_d = d;
+            }
+        }
+        pts.extend(best);
+    }
+    pts
+}
+
+/// One point in each cell of `grid`, moved at random by up to `amount` of the
+/// way to the edge of the cell on each axis, row by row.
+pub fn jittered_grid(rng: &mut dyn RngCore, grid: &Grid, amount: f64) -> Vec<Point> {
+    grid.indices()
+        .map(|(i, j)| grid.jittered(rng, i, j, amount))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    fn bounds() -> Rect {
+        Rect::from_ltrb(-50.0, 20.0, 350.0, 220.0)
+    }
+
+    fn min_distance(pts: &[Point]) -> f64 {
+        pts.iter()
+            .enumerate()
+            .flat_map(|(i, p)| pts[i + 1..].iter().map(move |q| p.distance(q)))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    #[test]
+    fn poisson_spacing() {
+        let mut rng = Pcg64::seed_from_u64(1);
+        for radius in [7.0, 20.0] {
+            let pts = PoissonDisk::new(bounds(), radius).sample(&mut rng);
+            assert!(min_distance(&pts) >= radius);
+            assert!(pts.iter().all(|p| bounds().contains(p)));
+            // a rect this size holds many more points than a handful.
+            let area = bounds().width() * bounds().height();
+            assert!(pts.len() as f64 > area / (radius * radius * 4.0));
+        }
+    }
+
+    #[test]
+    fn poisson_density() {
+        let mut rng = Pcg64::seed_from_u64(2);
+        let disk = PoissonDisk::new(bounds(), 6.0);
+        let left = |p: &Point| if p.x() < 150.0 { 1.0 } else { 0.0 };
+        let pts = disk.sample_with_density(&mut rng, 24.0, left);
+        assert!(min_distance(&pts) >= 6.0);
+        assert!(pts.iter().all(|p| bounds().contains(p)));
+        let l = pts.iter().filter(|p| p.x() < 150.0).count();
+        assert!(l > 4 * (pts.len() - l));
+
+        // a max radius that is too small is the same as the radius.
+        for max_radius in [f64::NAN, -1.0, 0.0] {
+            let pts = disk.sample_with_density(&mut rng, max_radius, left);
+            assert!(min_distance(&pts) >= 6.0);
+        }
+    }
+
+    #[test]
+    fn poisson_degenerate() {
+        let mut rng = Pcg64::seed_from_u64(3);
+        for radius in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(PoissonDisk::new(bounds(), radius)
+                .sample(&mut rng)
+                .is_empty());
+        }
+        let flat = Rect::from_ltrb(0.0, 10.0, 100.0, 10.0);
+        assert!(PoissonDisk::new(flat, 5.0).sample(&mut rng).is_empty());
+    }
+
+    #[test]
+    fn best_candidate_count() {
+        let mut rng = Pcg64::seed_from_u64(4);
+        for (n, candidates) in [(0, 10), (1, 10), (200, 10), (50, 0)] {
+            let pts = best_candidate(&mut rng, &bounds(), n, candidates);
+            assert_eq!(pts.len(), n);
+            assert!(pts.iter().all(|p| bounds().contains(p)));
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{error::SketchError, params::Param, RenderOpts};
+use cairo::Context;
+use clap::{parser::ValueSource, ArgAction, ArgMatches};
+
+/// A sketch that can be rendered by `draw` and `drawsome`. Each sketch becomes
+/// a subcommand of those tools, named after the sketch.
+pub trait Sketch: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    /// The tunable params the sketch resolves through `RenderOpts::params`.
+    fn params(&self) -> &'static [Param] {
+        &[]
+    }
+
+    /// Adds the sketch's own command line arguments to its subcommand. Sketches
+    /// with a `clap::Args` struct usually just call `augment_args` on it.
+    fn augment_args(&self, cmd: clap::Command) -> clap::Command {
+        cmd
+    }
+
+    /// Renders the sketch, where `args` are the matches for the arguments added
+    /// in `augment_args`.
+    fn render(
+        &self,
+        opts: &dyn RenderOpts,
+        ctx: &Context,
+        args: &ArgMatches,
+    ) -> Result<(), SketchError>;
+
+    fn command(&self) -> clap::Command {
+        self.augment_args(clap::Command::new(self.name()).about(self.description()))
+    }
+}
+
+/// The names of `draw`'s own subcommands, which sketches cannot use.
+pub const RESERVED_NAMES: &[&str] = &["replay", "themes"];
+
+/// The set of sketches that the command line tools know about.
+#[derive(Default)]
+pub struct Registry {
+    sketches: Vec<Box<dyn Sketch>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with all of the sketches in this crate.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register(crate::a::A)
+            .register(crate::b::B)
+            .register(crate::c::C)
+            .register(crate::d::D)
+            .register(crate::e::E)
+            .register(crate::f::F);
+        registry
+    }
+
+    /// Adds a sketch, replacing any existing sketch with the same name.
+    ///
+    /// # Panics
+    ///
+    /// If the sketch is named after one of `RESERVED_NAMES`, since `draw` would
+    /// never get to it.
+    pub fn register<S: Sketch + 'static>(&mut self, sketch: S) -> &mut Self {
+        assert!(
+            !RESERVED_NAMES.contains(&sketch.name()),
+            "cannot register a sketch named {:?}, the name is taken by a draw subcommand",
+            sketch.name()
+        );
+        self.sketches.retain(|s| s.name() != sketch.name());
+        self.sketches.push(Box::new(sketch));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Sketch> {
+        self.sketches
+            .iter()
+            .find(|s| s.name() == name)
+            .map(|s| s.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Sketch> {
+        self.sketches.iter().map(|s| s.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.sketches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sketches.is_empty()
+    }
+}
+
+/// Rebuilds the command line arguments that were explicitly given to a
+/// sketch's subcommand, so a render can be reproduced later.
+pub fn args_of(cmd: &clap::Command, matches: &ArgMatches) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut positionals = Vec::new();
+    for arg in cmd.get_arguments() {
+        let id = arg.get_id().as_str();
+        if matches.value_source(id) != Some(ValueSource::CommandLine) {
+            continue;
+        }
+        let values = matches
+            .get_raw(id)
+            .into_iter()
+            .flatten()
+            .map(|v| v.to_string_lossy().into_owned());
+        match arg.get_long() {
+            None => positionals.extend(values),
+            Some(long) => match arg.get_action() {
+                ArgAction::SetTrue | ArgAction::SetFalse => args.push(format!("--{}", long)),
+                ArgAction::Count => {
+                    args.extend((0..matches.get_count(id)).map(|_| format!("--{}", long)))
+                }
+                _ => args.extend(values.map(|v| format!("--{}={}", long, v))),
+            },
+        }
+    }
+    args.extend(positionals);
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Named(&'static str);
+
+    impl Sketch for Named {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn description(&self) -> &'static str {
+            "a sketch that draws nothing"
+        }
+
+        fn render(
+            &self,
+            _: &dyn RenderOpts,
+            _: &Context,
+            _: &ArgMatches,
+        ) -> Result<(), SketchError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn register_replaces() {
+        let mut registry = Registry::new();
+        registry
+            .register(Named("x"))
+            .register(Named("y"))
+            .register(Named("x"));
+        assert_eq!(registry.len(), 2);
+        assert!(registry.get("x").is_some());
+        assert!(registry.get("z").is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot register a sketch named \"replay\"")]
+    fn register_rejects_replay() {
+        Registry::new().register(Named("replay"));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot register a sketch named \"themes\"")]
+    fn register_rejects_themes() {
+        Registry::new().register(Named("themes"));
+    }
+}
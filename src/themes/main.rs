@@ -0,0 +1,6 @@
+use sketches::cli;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    cli::themes()
+}
@@ -0,0 +1,437 @@
+use crate::{Point, Rect};
+
+// The number of steps along each side of the triangles that cells are split
+// into when integrating a density over them.
+const WEIGHT_STEPS: usize = 4;
+
+/// A Delaunay triangulation of a set of points, built with the Bowyer-Watson
+/// algorithm. Triangles are indices into the points, with their corners in
+/// clockwise order on screen.
+///
+/// Building it takes O(n²) time, since each point is tested against every
+/// triangle so far rather than found by walking from a nearby one. That is
+/// fine for the few thousand points a sketch uses.
+#[derive(Debug, Clone)]
+pub struct Delaunay {
+    pts: Vec<Point>,
+    triangles: Vec<[usize; 3]>,
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl Delaunay {
+    pub fn new(pts: Vec<Point>) -> Self {
+        let n = pts.len();
+        let mut all = pts.clone();
+        all.extend(super_triangle(&pts));
+        let mut tris = vec![[n, n + 1, n + 2]];
+        for (i, p) in pts.iter().enumerate() {
+            let (bad, keep): (Vec<_>, Vec<_>) = std::mem::take(&mut tris)
+                .into_iter()
+                .partition(|t| in_circle(&all[t[0]], &all[t[1]], &all[t[2]], p));
+            // points that are already in the triangulation are left out.
+            if bad.is_empty() {
+                tris = keep;
+                continue;
+            }
+            let edges = bad
+                .iter()
+                .flat_map(|t| [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])]);
+            let boundary = edges
+                .clone()
+                .filter(|&(a, b)| !edges.clone().any(|e| e == (b, a)))
+                .collect::<Vec<_>>();
+            tris = keep;
+            tris.extend(boundary.into_iter().map(|(a, b)| [a, b, i]));
+        }
+
+        // neighbors come from every triangle, including those that touch the
+        // super triangle, so that points on the hull are joined up.
+        let mut neighbors = vec![Vec::new(); n];
+        for t in &tris {
+            for (a, b) in [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])] {
+                if a < n && b < n && !neighbors[a].contains(&b) {
+                    neighbors[a].push(b);
+                    neighbors[b].push(a);
+                }
+            }
+        }
+        tris.retain(|t| t.iter().all(|&v| v < n));
+        Self {
+            pts,
+            triangles: tris,
+            neighbors,
+        }
+    }
+
+    pub fn points(&self) -> &[Point] {
+        &self.pts
+    }
+
+    pub fn triangles(&self) -> &[[usize; 3]] {
+        &self.triangles
+    }
+
+    /// The corners of the `k`th triangle.
+    pub fn triangle(&self, k: usize) -> [Point; 3] {
+        self.triangles[k].map(|i| self.pts[i])
+    }
+
+    /// The center of the circle through the corners of the `k`th triangle,
+    /// which is a corner of the Voronoi diagram.
+    pub fn circumcenter(&self, k: usize) -> Point {
+        let [a, b, c] = self.triangle(k);
+        circumcenter(&a, &b, &c)
+    }
+
+    /// The indices of the points that share an edge with point `i`.
+    pub fn neighbors(&self, i: usize) -> &[usize] {
+        &self.neighbors[i]
+    }
+
+    /// Each edge of the triangulation once, as a pair of indices with the
+    /// lower first.
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbors
+            .iter()
+            .enumerate()
+            .flat_map(|(i, ns)| ns.iter().filter(move |&&j| i < j).map(move |&j| (i, j)))
+    }
+}
+
+/// The Voronoi diagram of a set of points, or sites, clipped to a rect. The
+/// cell of a site is the region that is closer to it than to any other site.
+#[derive(Debug, Clone)]
+pub struct Voronoi {
+    delaunay: Delaunay,
+    bounds: Rect,
+    cells: Vec<Vec<Point>>,
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl Voronoi {
+    /// The diagram of `pts`, which takes O(n²) time to triangulate as with
+    /// `Delaunay::new`.
+    pub fn new(pts: Vec<Point>, bounds: Rect) -> Self {
+        let delaunay = Delaunay::new(pts);
+        let n = delaunay.pts.len();
+        let (cells, neighbors) = (0..n)
+            .map(|i| {
+                // sites that were left out of the triangulation as duplicates
+                // of an earlier one have no neighbors, and no cell.
+                let pts = &delaunay.pts;
+                if delaunay.neighbors(i).is_empty() && pts[..i].contains(&pts[i]) {
+                    return (Vec::new(), Vec::new());
+                }
+                let cell = clip_cell(&delaunay, i, &bounds);
+                let mut ns = cell.iter().filter_map(|&(_, e)| e).collect::<Vec<_>>();
+                ns.dedup();
+                if ns.len() > 1 && ns.first() == ns.last() {
+                    ns.pop();
+                }
+                (cell.into_iter().map(|(p, _)| p).collect(), ns)
+            })
+            .unzip();
+        Self {
+            delaunay,
+            bounds,
+            cells,
+            neighbors,
+        }
+    }
+
+    pub fn delaunay(&self) -> &Delaunay {
+        &self.delaunay
+    }
+
+    pub fn sites(&self) -> &[Point] {
+        self.delaunay.points()
+    }
+
+    pub fn bounds(&self) -> &Rect {
+        &self.bounds
+    }
+
+    /// The corners of the cell of site `i`, clockwise. It is empty if the cell
+    /// is outside of the bounds.
+    pub fn cell(&self, i: usize) -> &[Point] {
+        &self.cells[i]
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = &[Point]> {
+        self.cells.iter().map(|c| c.as_slice())
+    }
+
+    /// The sites whose cells share an edge with the cell of site `i`, in
+    /// clockwise order around it.
+    pub fn neighbors(&self, i: usize) -> &[usize] {
+        &self.neighbors[i]
+    }
+
+    pub fn area(&self, i: usize) -> f64 {
+        area_centroid(&self.cells[i]).0
+    }
+
+    /// The center of mass of the cell of site `i`, or the site itself if the
+    /// cell is empty.
+    pub fn centroid(&self, i: usize) -> Point {
+        match area_centroid(&self.cells[i]) {
+            (a, c) if a > 0.0 => c,
+            _ => self.sites()[i],
+        }
+    }
+
+    /// The center of mass of the cell of site `i` when its density at each
+    /// point is given by `density`, or the site itself if the cell has no
+    /// mass.
+    pub fn weighted_centroid(&self, i: usize, density: impl Fn(&Point) -> f64) -> Point {
+        let cell = &self.cells[i];
+        let Some(&first) = cell.first() else {
+            return self.sites()[i];
+        };
+        let (mut mass, mut moment) = (0.0, Point::zero());
+        for w in cell[1..].windows(2) {
+            for (p, a) in split_triangle(&first, &w[0], &w[1]) {
+                let m = a * density(&p).max(0.0);
+                mass += m;
+                moment += p * m;
+            }
+        }
+        if mass > 0.0 {
+            moment / mass
+        } else {
+            self.sites()[i]
+        }
+    }
+
+    /// Lloyd's relaxation, which moves every site to the centroid of its cell
+    /// and rebuilds the diagram, `iterations` times. The cells become more
+    /// even with each one.
+    pub fn relax(&self, iterations: usize) -> Self {
+        self.relax_weighted(iterations, |_| 1.0)
+    }
+
+    /// Lloyd's relaxation toward weighted centroids, which gathers sites where
+    /// `density` is high, as in weighted Voronoi stippling. Each iteration
+    /// builds the diagram again, so costs as much as `new`.
+    pub fn relax_weighted(&self, iterations: usize, density: impl Fn(&Point) -> f64) -> Self {
+        let mut v = self.clone();
+        for _ in 0..iterations {
+            let pts = (0..v.sites().len())
+                .map(|i| v.weighted_centroid(i, &density))
+                .collect();
+            v = Self::new(pts, self.bounds);
+        }
+        v
+    }
+}
+
+// A triangle around all of `pts`, large enough that it doesn't disturb the
+// triangles on their hull.
+fn super_triangle(pts: &[Point]) -> [Point; 3] {
+    let bounds = pts
+        .iter()
+        .fold(None, |r: Option<Rect>, p| {
+            let pr = Rect::new(p, p);
+            Some(r.map_or(pr, |r| r.union(&pr)))
+        })
+        .unwrap_or_else(|| Rect::from_wh(1.0, 1.0));
+    let c = bounds.center();
+    let d = bounds.width().max(bounds.height()).max(1.0) * 100.0;
+    [
+        Point::new(c.x() - d, c.y() - d),
+        Point::new(c.x() + d, c.y() - d),
+        Point::new(c.x(), c.y() + d),
+    ]
+}
+
+// Whether `d` is strictly inside the circle through `a`, `b` and `c`, which
+// are clockwise on screen.
+fn in_circle(a: &Point, b: &Point, c: &Point, d: &Point) -> bool {
+    let (a, b, c) = (*a - *d, *b - *d, *c - *d);
+    let det = a.length_squared() * b.cross(&c) - b.length_squared() * a.cross(&c)
+        + c.length_squared() * a.cross(&b);
+    det > 0.0
+}
+
+fn circumcenter(a: &Point, b: &Point, c: &Point) -> Point {
+    let (b, c) = (*b - *a, *c - *a);
+    let d = 2.0 * b.cross(&c);
+    let (bl, cl) = (b.length_squared(), c.length_squared());
+    *a + Point::new(c.y() * bl - b.y() * cl, b.x() * cl - c.x() * bl) / d
+}
+
+// The cell of site `i`, as the bounds clipped to the half of the plane that
+// is closer to the site than to each neighbor in turn. Each corner carries
+// the neighbor whose bisector the edge from it runs along, if any.
+fn clip_cell(delaunay: &Delaunay, i: usize, bounds: &Rect) -> Vec<(Point, Option<usize>)> {
+    let mut cell = vec![
+        (*bounds.top_left(), None),
+        (bounds.top_right(), None),
+        (*bounds.bottom_right(), None),
+        (bounds.bottom_left(), None),
+    ];
+    let site = delaunay.pts[i];
+    for &j in delaunay.neighbors(i) {
+        let other = delaunay.pts[j];
+        let (mid, normal) = ((site + other) / 2.0, other - site);
+        let side = |p: &Point| (*p - mid).dot(&normal);
+        let mut next = Vec::with_capacity(cell.len() + 1);
+        for (k, &(p, e)) in cell.iter().enumerate() {
+            let q = cell[(k + 1) % cell.len()].0;
+            let (sp, sq) = (side(&p), side(&q));
+            let cross = || p.lerp(&q, sp / (sp - sq));
+            if sp <= 0.0 {
+                next.push((p, e));
+                if sq > 0.0 {
+                    next.push((cross(), Some(j)));
+                }
+            } else if sq <= 0.0 {
+                next.push((cross(), e));
+            }
+        }
+        cell = next;
+        if cell.is_empty() {
+            break;
+        }
+    }
+    cell
+}
+
+// The area of a polygon and its centroid.
+fn area_centroid(pts: &[Point]) -> (f64, Point) {
+    let Some(&first) = pts.first() else {
+        return (0.0, Point::zero());
+    };
+    let (mut area, mut moment) = (0.0, Point::zero());
+    for w in pts[1..].windows(2) {
+        let a = (w[0] - first).cross(&(w[1] - first)) / 2.0;
+        area += a;
+        moment += (first + w[0] + w[1]) / 3.0 * a;
+    }
+    if area == 0.0 {
+        (0.0, first)
+    } else {
+        (area.abs(), moment / area)
+    }
+}
+
+// Splits a triangle into smaller ones, with WEIGHT_STEPS along each side, and
+// gives the centroid and area of each.
+fn split_triangle(a: &Point, b: &Point, c: &Point) -> Vec<(Point, f64)> {
+    let n = WEIGHT_STEPS;
+    let area = (*b - *a).cross(&(*c - *a)).abs() / 2.0 / (n * n) as f64;
+    let (u, v) = ((*b - *a) / n as f64, (*c - *a) / n as f64);
+    let at = |i: f64, j: f64| *a + u * i + v * j;
+    let mut tris = Vec::with_capacity(n * n);
+    for i in 0..n {
+        for j in 0..n - i {
+            let (i, j) = (i as f64, j as f64);
+            tris.push((at(i + 1.0 / 3.0, j + 1.0 / 3.0), area));
+            if i + j + 2.0 <= n as f64 {
+                tris.push((at(i + 2.0 / 3.0, j + 2.0 / 3.0), area));
+            }
+        }
+    }
+    tris
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_pcg::Pcg64;
+
+    fn bounds() -> Rect {
+        Rect::from_ltrb(10.0, 20.0, 410.0, 320.0)
+    }
+
+    fn sites(seed: u64, n: usize) -> Vec<Point> {
+        let mut rng = Pcg64::seed_from_u64(seed);
+        (0..n)
+            .map(|_| Point::new(rng.gen_range(10.0..410.0), rng.gen_range(20.0..320.0)))
+            .collect()
+    }
+
+    fn total_area(v: &Voronoi) -> f64 {
+        (0..v.sites().len()).map(|i| v.area(i)).sum()
+    }
+
+    fn spread(v: &Voronoi) -> f64 {
+        let n = v.sites().len() as f64;
+        let areas = (0..v.sites().len()).map(|i| v.area(i)).collect::<Vec<_>>();
+        let mean = areas.iter().sum::<f64>() / n;
+        let var = areas.iter().map(|a| (a - mean).powi(2)).sum::<f64>() / n;
+        var.sqrt() / mean
+    }
+
+    #[test]
+    fn delaunay_is_empty_circle() {
+        let d = Delaunay::new(sites(1, 200));
+        assert!(!d.triangles().is_empty());
+        for t in d.triangles() {
+            let [a, b, c] = t.map(|i| d.points()[i]);
+            assert!((b - a).cross(&(c - a)) > 0.0);
+            for (i, p) in d.points().iter().enumerate() {
+                assert!(t.contains(&i) || !in_circle(&a, &b, &c, p));
+            }
+        }
+    }
+
+    #[test]
+    fn areas_cover_bounds() {
+        for seed in 0..4 {
+            let v = Voronoi::new(sites(seed, 150), bounds());
+            let area = bounds().width() * bounds().height();
+            assert!((total_area(&v) - area).abs() < 1e-6 * area);
+        }
+    }
+
+    #[test]
+    fn neighbors_symmetric() {
+        let v = Voronoi::new(sites(5, 150), bounds());
+        for i in 0..v.sites().len() {
+            assert!(!v.neighbors(i).is_empty());
+            for &j in v.neighbors(i) {
+                assert!(v.neighbors(j).contains(&i), "{} -> {}", i, j);
+            }
+        }
+        let d = v.delaunay();
+        for (i, j) in d.edges() {
+            assert!(d.neighbors(j).contains(&i));
+        }
+    }
+
+    #[test]
+    fn degenerate_sites() {
+        let area = bounds().width() * bounds().height();
+
+        let mut pts = sites(6, 40);
+        pts.extend(pts[..10].to_vec());
+        let v = Voronoi::new(pts, bounds());
+        assert!((total_area(&v) - area).abs() < 1e-6 * area);
+        assert!((40..50).all(|i| v.cell(i).is_empty()));
+
+        let line = (0..20)
+            .map(|i| Point::new(20.0 + i as f64 * 15.0, 100.0))
+            .collect::<Vec<_>>();
+        let v = Voronoi::new(line, bounds());
+        assert!(v.delaunay().triangles().is_empty());
+        assert!((total_area(&v) - area).abs() < 1e-6 * area);
+
+        assert_eq!(total_area(&Voronoi::new(vec![], bounds())), 0.0);
+        for n in [1, 3] {
+            let v = Voronoi::new(vec![Point::new(50.0, 50.0); n], bounds());
+            assert!((total_area(&v) - area).abs() < 1e-6 * area);
+            assert_eq!(v.cell(0).len(), 4);
+        }
+    }
+
+    #[test]
+    fn relax_evens_cells() {
+        let v = Voronoi::new(sites(7, 100), bounds());
+        let relaxed = v.relax(5);
+        assert!(spread(&relaxed) < spread(&v) / 2.0);
+        let area = bounds().width() * bounds().height();
+        assert!((total_area(&relaxed) - area).abs() < 1e-6 * area);
+    }
+}